@@ -0,0 +1,85 @@
+mod chars;
+mod errors;
+mod lists;
+mod macros;
+mod math;
+mod numbers;
+mod output;
+mod predicates;
+mod strings;
+mod system;
+
+pub use lists::list_items;
+pub use system::{load, set_command_line};
+
+use crate::error::LispError;
+use crate::number::Number;
+use crate::{Environment, LispValue};
+
+type Builtin = fn(&[LispValue]) -> Result<LispValue, LispError>;
+
+pub fn install(env: &Environment) {
+    system::set_global_environment(env);
+    for table in [
+        numbers::BUILTINS,
+        math::BUILTINS,
+        chars::BUILTINS,
+        lists::BUILTINS,
+        predicates::BUILTINS,
+        output::BUILTINS,
+        strings::BUILTINS,
+        system::BUILTINS,
+        errors::BUILTINS,
+        macros::BUILTINS,
+    ] {
+        for &(name, func) in table {
+            env.define(name.to_string(), LispValue::Builtin(name, func));
+        }
+    }
+    for &(name, control) in crate::CONTROLS {
+        env.define(name.to_string(), LispValue::Control(name, control));
+    }
+}
+
+pub fn check_arity(
+    name: &str,
+    args: &[LispValue],
+    min: usize,
+    max: Option<usize>,
+) -> Result<(), LispError> {
+    let expected = match max {
+        Some(max) if min == max => format!("{}", min),
+        Some(max) => format!("{} to {}", min, max),
+        None => format!("at least {}", min),
+    };
+    if args.len() < min || max.is_some_and(|max| args.len() > max) {
+        return Err(LispError::arity(name, expected, args.len()));
+    }
+    Ok(())
+}
+
+pub fn number(name: &str, value: &LispValue) -> Result<Number, LispError> {
+    match value {
+        LispValue::Number(num) => Ok(num.clone()),
+        _ => Err(LispError::type_error(name, "number", value)),
+    }
+}
+
+pub fn real(name: &str, value: &LispValue) -> Result<Number, LispError> {
+    match value {
+        LispValue::Number(num) if num.is_real() => Ok(num.clone()),
+        _ => Err(LispError::type_error(name, "real number", value)),
+    }
+}
+
+/// Reads an exact integer that fits in an `i64`, such as an index or a
+/// character code.
+pub fn integer(name: &str, value: &LispValue) -> Result<i64, LispError> {
+    match value {
+        LispValue::Number(Number::Integer(i)) => Ok(*i),
+        LispValue::Number(num) if num.is_exact() && num.is_integer() => Err(LispError::runtime(
+            format!("{}: integer out of range: {}", name, num),
+        )),
+        _ => Err(LispError::type_error(name, "exact integer", value)),
+    }
+}
@@ -0,0 +1,137 @@
+use super::{check_arity, integer, Builtin};
+use crate::error::LispError;
+use crate::number::Number;
+use crate::LispValue;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("char?", is_char),
+    ("char->integer", char_to_integer),
+    ("integer->char", integer_to_char),
+    ("char-alphabetic?", is_alphabetic),
+    ("char-numeric?", is_numeric),
+    ("char-whitespace?", is_whitespace),
+    ("char-upper-case?", is_upper_case),
+    ("char-lower-case?", is_lower_case),
+    ("char-upcase", char_upcase),
+    ("char-downcase", char_downcase),
+    ("char=?", char_eq),
+    ("char<?", char_lt),
+    ("char>?", char_gt),
+    ("char<=?", char_le),
+    ("char>=?", char_ge),
+];
+
+fn char(name: &str, value: &LispValue) -> Result<char, LispError> {
+    match value {
+        LispValue::Char(c) => Ok(*c),
+        _ => Err(LispError::type_error(name, "char", value)),
+    }
+}
+
+fn is_char(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("char?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(args[0], LispValue::Char(_))))
+}
+
+fn char_to_integer(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("char->integer", args, 1, Some(1))?;
+    let code = u32::from(char("char->integer", &args[0])?);
+    Ok(LispValue::Number(Number::Integer(code.into())))
+}
+
+fn integer_to_char(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("integer->char", args, 1, Some(1))?;
+    let code = integer("integer->char", &args[0])?;
+    match u32::try_from(code).ok().and_then(char::from_u32) {
+        Some(c) => Ok(LispValue::Char(c)),
+        _ => Err(LispError::runtime(format!(
+            "integer->char: not a Unicode scalar value: {}",
+            code
+        ))),
+    }
+}
+
+fn predicate(
+    name: &str,
+    args: &[LispValue],
+    test: fn(char) -> bool,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, Some(1))?;
+    Ok(LispValue::Boolean(test(char(name, &args[0])?)))
+}
+
+fn is_alphabetic(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("char-alphabetic?", args, char::is_alphabetic)
+}
+
+fn is_numeric(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("char-numeric?", args, char::is_numeric)
+}
+
+fn is_whitespace(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("char-whitespace?", args, char::is_whitespace)
+}
+
+fn is_upper_case(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("char-upper-case?", args, char::is_uppercase)
+}
+
+fn is_lower_case(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("char-lower-case?", args, char::is_lowercase)
+}
+
+/// Maps `c` through a case conversion, keeping it unchanged when the
+/// conversion does not produce exactly one character (as for `ß`).
+fn convert_case<I: Iterator<Item = char>>(c: char, mut converted: I) -> char {
+    match (converted.next(), converted.next()) {
+        (Some(single), None) => single,
+        _ => c,
+    }
+}
+
+fn char_upcase(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("char-upcase", args, 1, Some(1))?;
+    let c = char("char-upcase", &args[0])?;
+    Ok(LispValue::Char(convert_case(c, c.to_uppercase())))
+}
+
+fn char_downcase(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("char-downcase", args, 1, Some(1))?;
+    let c = char("char-downcase", &args[0])?;
+    Ok(LispValue::Char(convert_case(c, c.to_lowercase())))
+}
+
+fn compare(
+    name: &str,
+    args: &[LispValue],
+    op: fn(char, char) -> bool,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, None)?;
+    let chars = args
+        .iter()
+        .map(|arg| char(name, arg))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(LispValue::Boolean(
+        chars.windows(2).all(|pair| op(pair[0], pair[1])),
+    ))
+}
+
+fn char_eq(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("char=?", args, |a, b| a == b)
+}
+
+fn char_lt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("char<?", args, |a, b| a < b)
+}
+
+fn char_gt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("char>?", args, |a, b| a > b)
+}
+
+fn char_le(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("char<=?", args, |a, b| a <= b)
+}
+
+fn char_ge(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("char>=?", args, |a, b| a >= b)
+}
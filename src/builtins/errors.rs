@@ -0,0 +1,66 @@
+use super::{check_arity, Builtin};
+use crate::error::LispError;
+use crate::LispValue;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("raise", raise),
+    ("raise-continuable", raise_continuable),
+    ("error", error),
+    ("error-object?", is_error_object),
+    ("error-object-message", error_object_message),
+    ("error-object-irritants", error_object_irritants),
+];
+
+/// Raises any value. Raising a caught error object raises the original
+/// error again, keeping its location and backtrace.
+fn raise(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("raise", args, 1, Some(1))?;
+    match &args[0] {
+        LispValue::Error(err) => Err((**err).clone()),
+        value => Err(LispError::raise(value.clone())),
+    }
+}
+
+/// Raises any value. If the exception handler returns, its value is
+/// returned from the call.
+fn raise_continuable(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("raise-continuable", args, 1, Some(1))?;
+    Err(LispError::raise_continuable(args[0].clone()))
+}
+
+/// `(error message irritant ...)` raises an error object carrying the
+/// message and the irritants.
+fn error(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("error", args, 1, None)?;
+    let message = args[0].display().to_string();
+    Err(LispError::user(message, args[1..].to_vec()))
+}
+
+fn is_error_object(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("error-object?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(args[0], LispValue::Error(_))))
+}
+
+fn error_object_message(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("error-object-message", args, 1, Some(1))?;
+    match &args[0] {
+        LispValue::Error(err) => Ok(LispValue::String(err.message().into())),
+        other => Err(LispError::type_error(
+            "error-object-message",
+            "error object",
+            other,
+        )),
+    }
+}
+
+fn error_object_irritants(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("error-object-irritants", args, 1, Some(1))?;
+    match &args[0] {
+        LispValue::Error(err) => Ok(LispValue::list(err.irritants().to_vec())),
+        other => Err(LispError::type_error(
+            "error-object-irritants",
+            "error object",
+            other,
+        )),
+    }
+}
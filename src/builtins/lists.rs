@@ -0,0 +1,152 @@
+use super::{check_arity, integer, Builtin};
+use crate::error::LispError;
+use crate::number::Number;
+use crate::{LispValue, Pair};
+use std::rc::Rc;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("cons", cons),
+    ("car", car),
+    ("cdr", cdr),
+    ("set-car!", set_car),
+    ("set-cdr!", set_cdr),
+    ("list", list),
+    ("length", length),
+    ("append", append),
+    ("reverse", reverse),
+    ("list-ref", list_ref),
+    ("null?", is_null),
+    ("pair?", is_pair),
+    ("list?", is_list),
+];
+
+/// Collects the elements of a proper list, failing on improper or circular
+/// lists.
+pub fn list_items(name: &str, value: &LispValue) -> Result<Vec<LispValue>, LispError> {
+    let mut items = Vec::new();
+    let mut current = value.clone();
+    let mut slow = value.clone();
+    loop {
+        match current {
+            LispValue::Nil => return Ok(items),
+            LispValue::Pair(pair) => {
+                items.push(pair.car.borrow().clone());
+                current = pair.cdr.borrow().clone();
+            }
+            _ => return Err(LispError::type_error(name, "list", value)),
+        }
+        // Move `slow` at half speed; meeting `current` means a cycle.
+        if items.len() % 2 == 0 {
+            if let LispValue::Pair(pair) = slow {
+                slow = pair.cdr.borrow().clone();
+            }
+            if let (LispValue::Pair(a), LispValue::Pair(b)) = (&slow, &current) {
+                if Rc::ptr_eq(a, b) {
+                    return Err(LispError::type_error(name, "list", value));
+                }
+            }
+        }
+    }
+}
+
+fn pair<'a>(name: &str, value: &'a LispValue) -> Result<&'a Rc<Pair>, LispError> {
+    match value {
+        LispValue::Pair(pair) => Ok(pair),
+        _ => Err(LispError::type_error(name, "pair", value)),
+    }
+}
+
+fn cons(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("cons", args, 2, Some(2))?;
+    Ok(LispValue::cons(args[0].clone(), args[1].clone()))
+}
+
+fn car(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("car", args, 1, Some(1))?;
+    Ok(pair("car", &args[0])?.car.borrow().clone())
+}
+
+fn cdr(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("cdr", args, 1, Some(1))?;
+    Ok(pair("cdr", &args[0])?.cdr.borrow().clone())
+}
+
+fn set_car(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("set-car!", args, 2, Some(2))?;
+    *pair("set-car!", &args[0])?.car.borrow_mut() = args[1].clone();
+    Ok(LispValue::Unspecified)
+}
+
+fn set_cdr(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("set-cdr!", args, 2, Some(2))?;
+    *pair("set-cdr!", &args[0])?.cdr.borrow_mut() = args[1].clone();
+    Ok(LispValue::Unspecified)
+}
+
+fn list(args: &[LispValue]) -> Result<LispValue, LispError> {
+    Ok(LispValue::list(args.to_vec()))
+}
+
+fn length(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("length", args, 1, Some(1))?;
+    let length = list_items("length", &args[0])?.len();
+    Ok(LispValue::Number(Number::Integer(length as i64)))
+}
+
+/// Every argument but the last is copied; the last becomes the shared tail.
+fn append(args: &[LispValue]) -> Result<LispValue, LispError> {
+    let (last, init) = match args.split_last() {
+        Some(split) => split,
+        None => return Ok(LispValue::Nil),
+    };
+    let mut items = Vec::new();
+    for arg in init {
+        items.extend(list_items("append", arg)?);
+    }
+    Ok(LispValue::list_with_tail(items, last.clone()))
+}
+
+fn reverse(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("reverse", args, 1, Some(1))?;
+    let mut items = list_items("reverse", &args[0])?;
+    items.reverse();
+    Ok(LispValue::list(items))
+}
+
+fn list_ref(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("list-ref", args, 2, Some(2))?;
+    let index = integer("list-ref", &args[1])?;
+    if index < 0 {
+        return Err(LispError::runtime(format!(
+            "list-ref: index out of range: {}",
+            index
+        )));
+    }
+    let mut current = args[0].clone();
+    for _ in 0..index as usize {
+        let next = pair("list-ref", &current)?.cdr.borrow().clone();
+        current = next;
+    }
+    match &current {
+        LispValue::Pair(pair) => Ok(pair.car.borrow().clone()),
+        _ => Err(LispError::runtime(format!(
+            "list-ref: index out of range: {}",
+            index
+        ))),
+    }
+}
+
+fn is_null(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("null?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(args[0], LispValue::Nil)))
+}
+
+fn is_pair(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("pair?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(args[0], LispValue::Pair(_))))
+}
+
+fn is_list(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("list?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(list_items("list?", &args[0]).is_ok()))
+}
@@ -0,0 +1,106 @@
+use super::system::global_environment;
+use super::{check_arity, list_items, Builtin};
+use crate::error::LispError;
+use crate::syntax::{self, SyntaxObject};
+use crate::{LispExpression, LispValue};
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("macroexpand-1", macroexpand_1),
+    ("macroexpand", macroexpand),
+    ("identifier?", is_identifier),
+    ("syntax->datum", syntax_to_datum),
+    ("datum->syntax", datum_to_syntax),
+    ("generate-temporaries", generate_temporaries),
+    ("free-identifier=?", free_identifier_eq),
+];
+
+/// `(macroexpand-1 'form)` expands `form` once if it is a use of a global
+/// macro, and returns it unchanged otherwise.
+fn macroexpand_1(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("macroexpand-1", args, 1, Some(1))?;
+    let form = LispExpression::from_value(&args[0], None)?;
+    match crate::macroexpand_1(&form, &global_environment("macroexpand-1")?)? {
+        Some(expansion) => Ok(LispValue::from_datum(&expansion)),
+        None => Ok(args[0].clone()),
+    }
+}
+
+/// `(macroexpand 'form)` expands `form` repeatedly until it is no longer a
+/// macro use. Subforms are left as they are.
+fn macroexpand(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("macroexpand", args, 1, Some(1))?;
+    let env = global_environment("macroexpand")?;
+    let mut form = LispExpression::from_value(&args[0], None)?;
+    while let Some(expansion) = crate::macroexpand_1(&form, &env)? {
+        form = expansion;
+    }
+    Ok(LispValue::from_datum(&form))
+}
+
+fn identifier<'a>(name: &str, value: &'a LispValue) -> Result<&'a SyntaxObject, LispError> {
+    match value {
+        LispValue::SyntaxObject(syntax) if matches!(syntax.expr, LispExpression::Symbol(_)) => {
+            Ok(syntax)
+        }
+        other => Err(LispError::type_error(name, "identifier", other)),
+    }
+}
+
+fn is_identifier(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("identifier?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(
+        identifier("identifier?", &args[0]).is_ok(),
+    ))
+}
+
+/// `(syntax->datum syntax)` strips the lexical context from syntax, which
+/// may also be a list containing syntax objects.
+fn syntax_to_datum(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("syntax->datum", args, 1, Some(1))?;
+    match LispExpression::from_value(&args[0], None) {
+        Ok(expr) => Ok(LispValue::from_datum(&expr)),
+        Err(_) => Ok(args[0].clone()),
+    }
+}
+
+/// `(datum->syntax identifier datum)` makes syntax from `datum` whose
+/// symbols bind and refer as if they were written where `identifier` was.
+fn datum_to_syntax(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("datum->syntax", args, 2, Some(2))?;
+    syntax::datum_to_syntax(identifier("datum->syntax", &args[0])?, &args[1])
+}
+
+/// `(generate-temporaries list)` returns as many fresh identifiers as the
+/// list, or the syntax of a list, has elements.
+fn generate_temporaries(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("generate-temporaries", args, 1, Some(1))?;
+    let env = global_environment("generate-temporaries")?;
+    let count = match &args[0] {
+        LispValue::SyntaxObject(syntax) => match &syntax.expr {
+            LispExpression::List(items) => items.len(),
+            _ => {
+                return Err(LispError::type_error(
+                    "generate-temporaries",
+                    "list",
+                    &args[0],
+                ))
+            }
+        },
+        list => list_items("generate-temporaries", list)?.len(),
+    };
+    Ok(LispValue::list(syntax::generate_temporaries(count, &env)))
+}
+
+fn free_identifier_eq(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("free-identifier=?", args, 2, Some(2))?;
+    let (a, b) = (
+        identifier("free-identifier=?", &args[0])?,
+        identifier("free-identifier=?", &args[1])?,
+    );
+    Ok(LispValue::Boolean(match (&a.expr, &b.expr) {
+        (LispExpression::Symbol(x), LispExpression::Symbol(y)) => {
+            syntax::free_identifier_eq(x, &a.env, y, &b.env)
+        }
+        _ => false,
+    }))
+}
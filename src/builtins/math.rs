@@ -0,0 +1,175 @@
+use super::{check_arity, number, real, Builtin};
+use crate::error::LispError;
+use crate::number::Number;
+use crate::LispValue;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("sqrt", sqrt),
+    ("exact-integer-sqrt", exact_integer_sqrt),
+    ("exp", exp),
+    ("log", log),
+    ("sin", sin),
+    ("cos", cos),
+    ("tan", tan),
+    ("asin", asin),
+    ("acos", acos),
+    ("atan", atan),
+    ("expt", expt),
+    ("square", square),
+    ("gcd", gcd),
+    ("lcm", lcm),
+    ("make-rectangular", make_rectangular),
+    ("make-polar", make_polar),
+    ("real-part", real_part),
+    ("imag-part", imag_part),
+    ("magnitude", magnitude),
+    ("angle", angle),
+];
+
+fn unary(
+    name: &str,
+    args: &[LispValue],
+    op: fn(&Number) -> Number,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, Some(1))?;
+    Ok(LispValue::Number(op(&number(name, &args[0])?)))
+}
+
+fn sqrt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("sqrt", args, Number::sqrt)
+}
+
+/// Returns the root and the remainder as two values.
+fn exact_integer_sqrt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("exact-integer-sqrt", args, 1, Some(1))?;
+    let (root, rest) = number("exact-integer-sqrt", &args[0])?
+        .exact_integer_sqrt()
+        .map_err(|err| LispError::runtime(format!("exact-integer-sqrt: {}", err)))?;
+    Ok(LispValue::values(vec![
+        LispValue::Number(root),
+        LispValue::Number(rest),
+    ]))
+}
+
+fn exp(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("exp", args, Number::exp)
+}
+
+/// `(log z)` is the natural logarithm; `(log z base)` divides by `(log base)`.
+fn log(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("log", args, 1, Some(2))?;
+    let z = number("log", &args[0])?;
+    if z.is_exact() && z.is_zero() {
+        return Err(LispError::runtime("log: undefined for exact zero"));
+    }
+    match args.get(1) {
+        None => Ok(LispValue::Number(z.ln())),
+        Some(base) => {
+            let base = number("log", base)?;
+            z.ln()
+                .div(&base.ln())
+                .map(LispValue::Number)
+                .map_err(|err| LispError::runtime(format!("log: {}", err)))
+        }
+    }
+}
+
+fn sin(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("sin", args, Number::sin)
+}
+
+fn cos(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("cos", args, Number::cos)
+}
+
+fn tan(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("tan", args, Number::tan)
+}
+
+fn asin(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("asin", args, Number::asin)
+}
+
+fn acos(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("acos", args, Number::acos)
+}
+
+/// With two arguments `(atan y x)` gives the angle of the point `(x, y)`.
+fn atan(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("atan", args, 1, Some(2))?;
+    match args {
+        [z] => Ok(LispValue::Number(number("atan", z)?.atan())),
+        [y, x] => {
+            let (y, x) = (real("atan", y)?, real("atan", x)?);
+            Ok(LispValue::Number(Number::Real(
+                y.to_f64().atan2(x.to_f64()),
+            )))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn expt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("expt", args, 2, Some(2))?;
+    let base = number("expt", &args[0])?;
+    let power = number("expt", &args[1])?;
+    base.expt(&power)
+        .map(LispValue::Number)
+        .map_err(|err| LispError::runtime(format!("expt: {}", err)))
+}
+
+fn square(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("square", args, |z| z.mul(z))
+}
+
+fn integer_fold(
+    name: &str,
+    args: &[LispValue],
+    init: Number,
+    op: fn(&Number, &Number) -> Result<Number, String>,
+) -> Result<LispValue, LispError> {
+    let mut result = init;
+    for arg in args {
+        result = op(&result, &number(name, arg)?)
+            .map_err(|err| LispError::runtime(format!("{}: {}", name, err)))?;
+    }
+    Ok(LispValue::Number(result))
+}
+
+fn gcd(args: &[LispValue]) -> Result<LispValue, LispError> {
+    integer_fold("gcd", args, Number::Integer(0), Number::gcd)
+}
+
+fn lcm(args: &[LispValue]) -> Result<LispValue, LispError> {
+    integer_fold("lcm", args, Number::Integer(1), Number::lcm)
+}
+
+fn make_rectangular(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("make-rectangular", args, 2, Some(2))?;
+    let re = real("make-rectangular", &args[0])?;
+    let im = real("make-rectangular", &args[1])?;
+    Ok(LispValue::Number(Number::make_rectangular(&re, &im)))
+}
+
+fn make_polar(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("make-polar", args, 2, Some(2))?;
+    let magnitude = real("make-polar", &args[0])?;
+    let angle = real("make-polar", &args[1])?;
+    Ok(LispValue::Number(Number::make_polar(&magnitude, &angle)))
+}
+
+fn real_part(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("real-part", args, Number::real_part)
+}
+
+fn imag_part(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("imag-part", args, Number::imag_part)
+}
+
+fn magnitude(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("magnitude", args, Number::magnitude)
+}
+
+fn angle(args: &[LispValue]) -> Result<LispValue, LispError> {
+    unary("angle", args, Number::angle)
+}
@@ -0,0 +1,331 @@
+use super::{check_arity, number, real, Builtin};
+use crate::error::LispError;
+use crate::number::Number;
+use crate::LispValue;
+use std::cmp::Ordering;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("+", add),
+    ("-", sub),
+    ("*", mul),
+    ("/", div),
+    ("=", num_eq),
+    ("<", lt),
+    (">", gt),
+    ("<=", le),
+    (">=", ge),
+    ("abs", abs),
+    ("min", min),
+    ("max", max),
+    ("quotient", quotient),
+    ("remainder", remainder),
+    ("modulo", modulo),
+    ("numerator", numerator),
+    ("denominator", denominator),
+    ("floor", floor),
+    ("ceiling", ceiling),
+    ("round", round),
+    ("truncate", truncate),
+    ("rationalize", rationalize),
+    ("zero?", is_zero),
+    ("positive?", is_positive),
+    ("negative?", is_negative),
+    ("odd?", is_odd),
+    ("even?", is_even),
+    ("number?", is_number),
+    ("complex?", is_number),
+    ("real?", is_real),
+    ("integer?", is_integer),
+    ("rational?", is_rational),
+    ("exact-integer?", is_exact_integer),
+    ("exact?", is_exact),
+    ("inexact?", is_inexact),
+    ("exact", exact),
+    ("inexact->exact", exact),
+    ("inexact", inexact),
+    ("exact->inexact", inexact),
+];
+
+fn numbers(name: &str, args: &[LispValue]) -> Result<Vec<Number>, LispError> {
+    args.iter().map(|arg| number(name, arg)).collect()
+}
+
+fn fold(
+    name: &str,
+    args: &[LispValue],
+    init: Number,
+    op: fn(&Number, &Number) -> Result<Number, String>,
+) -> Result<LispValue, LispError> {
+    let mut result = init;
+    for num in numbers(name, args)? {
+        result =
+            op(&result, &num).map_err(|err| LispError::runtime(format!("{}: {}", name, err)))?;
+    }
+    Ok(LispValue::Number(result))
+}
+
+fn add(args: &[LispValue]) -> Result<LispValue, LispError> {
+    fold("+", args, Number::Integer(0), |a, b| Ok(a.add(b)))
+}
+
+fn mul(args: &[LispValue]) -> Result<LispValue, LispError> {
+    fold("*", args, Number::Integer(1), |a, b| Ok(a.mul(b)))
+}
+
+fn sub(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("-", args, 1, None)?;
+    if args.len() == 1 {
+        return Ok(LispValue::Number(number("-", &args[0])?.neg()));
+    }
+    fold("-", &args[1..], number("-", &args[0])?, |a, b| Ok(a.sub(b)))
+}
+
+fn div(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("/", args, 1, None)?;
+    if args.len() == 1 {
+        return fold("/", args, Number::Integer(1), Number::div);
+    }
+    fold("/", &args[1..], number("/", &args[0])?, Number::div)
+}
+
+fn reals(name: &str, args: &[LispValue]) -> Result<Vec<Number>, LispError> {
+    args.iter().map(|arg| real(name, arg)).collect()
+}
+
+fn compare(
+    name: &str,
+    args: &[LispValue],
+    accept: fn(Ordering) -> bool,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, None)?;
+    let nums = reals(name, args)?;
+    Ok(LispValue::Boolean(
+        nums.windows(2)
+            .all(|pair| pair[0].compare(&pair[1]).is_some_and(accept)),
+    ))
+}
+
+fn num_eq(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("=", args, 1, None)?;
+    let nums = numbers("=", args)?;
+    Ok(LispValue::Boolean(
+        nums.windows(2).all(|pair| pair[0].equals(&pair[1])),
+    ))
+}
+
+fn lt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("<", args, Ordering::is_lt)
+}
+
+fn gt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare(">", args, Ordering::is_gt)
+}
+
+fn le(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("<=", args, Ordering::is_le)
+}
+
+fn ge(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare(">=", args, Ordering::is_ge)
+}
+
+fn abs(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("abs", args, 1, Some(1))?;
+    Ok(LispValue::Number(real("abs", &args[0])?.abs()))
+}
+
+/// Picks the extreme argument; the result is inexact if any argument is.
+fn extremum(name: &str, args: &[LispValue], keep: Ordering) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, None)?;
+    let nums = reals(name, args)?;
+    let mut result = nums[0].clone();
+    for num in &nums[1..] {
+        if num.compare(&result) == Some(keep) {
+            result = num.clone();
+        }
+    }
+    if nums.iter().any(|num| !num.is_exact()) {
+        result = result.to_inexact();
+    }
+    Ok(LispValue::Number(result))
+}
+
+fn min(args: &[LispValue]) -> Result<LispValue, LispError> {
+    extremum("min", args, Ordering::Less)
+}
+
+fn max(args: &[LispValue]) -> Result<LispValue, LispError> {
+    extremum("max", args, Ordering::Greater)
+}
+
+fn integer_division(
+    name: &str,
+    args: &[LispValue],
+    op: fn(&Number, &Number) -> Result<Number, String>,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 2, Some(2))?;
+    let dividend = number(name, &args[0])?;
+    let divisor = number(name, &args[1])?;
+    op(&dividend, &divisor)
+        .map(LispValue::Number)
+        .map_err(|err| LispError::runtime(format!("{}: {}", name, err)))
+}
+
+fn quotient(args: &[LispValue]) -> Result<LispValue, LispError> {
+    integer_division("quotient", args, Number::quotient)
+}
+
+fn remainder(args: &[LispValue]) -> Result<LispValue, LispError> {
+    integer_division("remainder", args, Number::remainder)
+}
+
+fn modulo(args: &[LispValue]) -> Result<LispValue, LispError> {
+    integer_division("modulo", args, Number::modulo)
+}
+
+fn numerator(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("numerator", args, 1, Some(1))?;
+    let (numerator, _) = real("numerator", &args[0])?
+        .fraction()
+        .map_err(|err| LispError::runtime(format!("numerator: {}", err)))?;
+    Ok(LispValue::Number(numerator))
+}
+
+fn denominator(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("denominator", args, 1, Some(1))?;
+    let (_, denominator) = real("denominator", &args[0])?
+        .fraction()
+        .map_err(|err| LispError::runtime(format!("denominator: {}", err)))?;
+    Ok(LispValue::Number(denominator))
+}
+
+fn rounding(
+    name: &str,
+    args: &[LispValue],
+    op: fn(&Number) -> Number,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, Some(1))?;
+    Ok(LispValue::Number(op(&real(name, &args[0])?)))
+}
+
+fn floor(args: &[LispValue]) -> Result<LispValue, LispError> {
+    rounding("floor", args, Number::floor)
+}
+
+fn ceiling(args: &[LispValue]) -> Result<LispValue, LispError> {
+    rounding("ceiling", args, Number::ceiling)
+}
+
+fn round(args: &[LispValue]) -> Result<LispValue, LispError> {
+    rounding("round", args, Number::round)
+}
+
+fn truncate(args: &[LispValue]) -> Result<LispValue, LispError> {
+    rounding("truncate", args, Number::truncate)
+}
+
+fn rationalize(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("rationalize", args, 2, Some(2))?;
+    let x = real("rationalize", &args[0])?;
+    let y = real("rationalize", &args[1])?;
+    x.rationalize(&y)
+        .map(LispValue::Number)
+        .map_err(|err| LispError::runtime(format!("rationalize: {}", err)))
+}
+
+fn predicate(
+    name: &str,
+    args: &[LispValue],
+    test: fn(&Number) -> bool,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, Some(1))?;
+    Ok(LispValue::Boolean(test(&number(name, &args[0])?)))
+}
+
+fn is_zero(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("zero?", args, Number::is_zero)
+}
+
+fn is_positive(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("positive?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(
+        real("positive?", &args[0])?.is_positive(),
+    ))
+}
+
+fn is_negative(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("negative?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(
+        real("negative?", &args[0])?.is_negative(),
+    ))
+}
+
+fn is_odd(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("odd?", args, 1, Some(1))?;
+    let rem = number("odd?", &args[0])?
+        .remainder(&Number::Integer(2))
+        .map_err(|err| LispError::runtime(format!("odd?: {}", err)))?;
+    Ok(LispValue::Boolean(!rem.is_zero()))
+}
+
+fn is_even(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("even?", args, 1, Some(1))?;
+    let rem = number("even?", &args[0])?
+        .remainder(&Number::Integer(2))
+        .map_err(|err| LispError::runtime(format!("even?: {}", err)))?;
+    Ok(LispValue::Boolean(rem.is_zero()))
+}
+
+fn is_number(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("number?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(args[0], LispValue::Number(_))))
+}
+
+fn is_real(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("real?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(
+        matches!(&args[0], LispValue::Number(num) if num.is_real()),
+    ))
+}
+
+fn is_integer(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("integer?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(
+        matches!(&args[0], LispValue::Number(num) if num.is_integer()),
+    ))
+}
+
+fn is_rational(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("rational?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(
+        matches!(&args[0], LispValue::Number(num) if num.is_exact() || num.to_f64().is_finite()),
+    ))
+}
+
+fn is_exact_integer(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("exact-integer?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(
+        matches!(&args[0], LispValue::Number(num) if num.is_exact() && num.is_integer()),
+    ))
+}
+
+fn is_exact(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("exact?", args, Number::is_exact)
+}
+
+fn is_inexact(args: &[LispValue]) -> Result<LispValue, LispError> {
+    predicate("inexact?", args, |num| !num.is_exact())
+}
+
+fn exact(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("exact", args, 1, Some(1))?;
+    number("exact", &args[0])?
+        .to_exact()
+        .map(LispValue::Number)
+        .map_err(|err| LispError::runtime(format!("exact: {}", err)))
+}
+
+fn inexact(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("inexact", args, 1, Some(1))?;
+    Ok(LispValue::Number(number("inexact", &args[0])?.to_inexact()))
+}
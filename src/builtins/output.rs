@@ -0,0 +1,52 @@
+use super::{check_arity, Builtin};
+use crate::error::LispError;
+use crate::LispValue;
+use std::io::{self, Write};
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("display", display),
+    ("write", write),
+    ("newline", newline),
+    ("write-char", write_char),
+    ("write-string", write_string),
+];
+
+fn output(text: &str) -> Result<LispValue, LispError> {
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(text.as_bytes())
+        .and_then(|()| stdout.flush())
+        .map_err(|err| LispError::runtime(format!("output error: {}", err)))?;
+    Ok(LispValue::Unspecified)
+}
+
+fn display(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("display", args, 1, Some(1))?;
+    output(&args[0].display().to_string())
+}
+
+fn write(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("write", args, 1, Some(1))?;
+    output(&args[0].to_string())
+}
+
+fn newline(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("newline", args, 0, Some(0))?;
+    output("\n")
+}
+
+fn write_char(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("write-char", args, 1, Some(1))?;
+    match &args[0] {
+        LispValue::Char(c) => output(&c.to_string()),
+        other => Err(LispError::type_error("write-char", "char", other)),
+    }
+}
+
+fn write_string(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("write-string", args, 1, Some(1))?;
+    match &args[0] {
+        LispValue::String(string) => output(string),
+        other => Err(LispError::type_error("write-string", "string", other)),
+    }
+}
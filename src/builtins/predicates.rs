@@ -0,0 +1,34 @@
+use super::{check_arity, Builtin};
+use crate::error::LispError;
+use crate::LispValue;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("eq?", is_eqv),
+    ("eqv?", is_eqv),
+    ("equal?", is_equal),
+    ("not", not),
+    ("symbol?", is_symbol),
+];
+
+fn is_eqv(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("eqv?", args, 2, Some(2))?;
+    Ok(LispValue::Boolean(args[0].eqv(&args[1])))
+}
+
+fn is_equal(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("equal?", args, 2, Some(2))?;
+    Ok(LispValue::Boolean(args[0].equal(&args[1])))
+}
+
+fn not(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("not", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(
+        args[0],
+        LispValue::Boolean(false)
+    )))
+}
+
+fn is_symbol(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("symbol?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(args[0], LispValue::Symbol(_))))
+}
@@ -0,0 +1,238 @@
+use super::{check_arity, integer, list_items, number, Builtin};
+use crate::error::LispError;
+use crate::number::Number;
+use crate::LispValue;
+use std::rc::Rc;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("string?", is_string),
+    ("string-length", string_length),
+    ("string-append", string_append),
+    ("substring", substring),
+    ("string-ref", string_ref),
+    ("string->list", string_to_list),
+    ("list->string", list_to_string),
+    ("string=?", string_eq),
+    ("string<?", string_lt),
+    ("string>?", string_gt),
+    ("string<=?", string_le),
+    ("string>=?", string_ge),
+    ("string-upcase", string_upcase),
+    ("string-downcase", string_downcase),
+    ("string-split", string_split),
+    ("string-join", string_join),
+    ("string->number", string_to_number),
+    ("number->string", number_to_string),
+    ("string->symbol", string_to_symbol),
+    ("symbol->string", symbol_to_string),
+];
+
+fn string(name: &str, value: &LispValue) -> Result<Rc<str>, LispError> {
+    match value {
+        LispValue::String(string) => Ok(string.clone()),
+        _ => Err(LispError::type_error(name, "string", value)),
+    }
+}
+
+/// Reads a character index into `string`, allowing `string`'s length itself.
+fn index(name: &str, value: &LispValue, string: &str) -> Result<usize, LispError> {
+    let index = integer(name, value)?;
+    let length = string.chars().count();
+    if index < 0 || index as usize > length {
+        return Err(LispError::runtime(format!(
+            "{}: index out of range: {}",
+            name, index
+        )));
+    }
+    Ok(index as usize)
+}
+
+fn is_string(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string?", args, 1, Some(1))?;
+    Ok(LispValue::Boolean(matches!(args[0], LispValue::String(_))))
+}
+
+fn string_length(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string-length", args, 1, Some(1))?;
+    let string = string("string-length", &args[0])?;
+    Ok(LispValue::Number(Number::Integer(
+        string.chars().count() as i64
+    )))
+}
+
+fn string_append(args: &[LispValue]) -> Result<LispValue, LispError> {
+    let mut result = String::new();
+    for arg in args {
+        result.push_str(&string("string-append", arg)?);
+    }
+    Ok(LispValue::String(result.into()))
+}
+
+fn substring(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("substring", args, 2, Some(3))?;
+    let string = string("substring", &args[0])?;
+    let start = index("substring", &args[1], &string)?;
+    let end = match args.get(2) {
+        Some(end) => index("substring", end, &string)?,
+        None => string.chars().count(),
+    };
+    if start > end {
+        return Err(LispError::runtime(format!(
+            "substring: start {} is after end {}",
+            start, end
+        )));
+    }
+    let result: String = string.chars().skip(start).take(end - start).collect();
+    Ok(LispValue::String(result.into()))
+}
+
+fn string_ref(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string-ref", args, 2, Some(2))?;
+    let string = string("string-ref", &args[0])?;
+    let k = integer("string-ref", &args[1])?;
+    match string.chars().nth(k as usize) {
+        Some(c) if k >= 0 => Ok(LispValue::Char(c)),
+        _ => Err(LispError::runtime(format!(
+            "string-ref: index out of range: {}",
+            k
+        ))),
+    }
+}
+
+fn string_to_list(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string->list", args, 1, Some(1))?;
+    let string = string("string->list", &args[0])?;
+    Ok(LispValue::list(
+        string.chars().map(LispValue::Char).collect(),
+    ))
+}
+
+fn list_to_string(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("list->string", args, 1, Some(1))?;
+    let string = list_items("list->string", &args[0])?
+        .iter()
+        .map(|item| match item {
+            LispValue::Char(c) => Ok(*c),
+            _ => Err(LispError::type_error("list->string", "char", item)),
+        })
+        .collect::<Result<String, _>>()?;
+    Ok(LispValue::String(string.into()))
+}
+
+fn compare(
+    name: &str,
+    args: &[LispValue],
+    op: fn(&str, &str) -> bool,
+) -> Result<LispValue, LispError> {
+    check_arity(name, args, 1, None)?;
+    let strings = args
+        .iter()
+        .map(|arg| string(name, arg))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(LispValue::Boolean(
+        strings.windows(2).all(|pair| op(&pair[0], &pair[1])),
+    ))
+}
+
+fn string_eq(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("string=?", args, |a, b| a == b)
+}
+
+fn string_lt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("string<?", args, |a, b| a < b)
+}
+
+fn string_gt(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("string>?", args, |a, b| a > b)
+}
+
+fn string_le(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("string<=?", args, |a, b| a <= b)
+}
+
+fn string_ge(args: &[LispValue]) -> Result<LispValue, LispError> {
+    compare("string>=?", args, |a, b| a >= b)
+}
+
+fn string_upcase(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string-upcase", args, 1, Some(1))?;
+    Ok(LispValue::String(
+        string("string-upcase", &args[0])?.to_uppercase().into(),
+    ))
+}
+
+fn string_downcase(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string-downcase", args, 1, Some(1))?;
+    Ok(LispValue::String(
+        string("string-downcase", &args[0])?.to_lowercase().into(),
+    ))
+}
+
+/// `(string-split s)` splits on runs of whitespace; `(string-split s sep)`
+/// splits on every occurrence of `sep`, a string or a char.
+fn string_split(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string-split", args, 1, Some(2))?;
+    let string = string("string-split", &args[0])?;
+    let parts: Vec<&str> = match args.get(1) {
+        Some(LispValue::Char(separator)) => string.split(*separator).collect(),
+        Some(separator) => {
+            let separator = self::string("string-split", separator)?;
+            if separator.is_empty() {
+                return Err(LispError::runtime(
+                    "string-split: separator must not be empty",
+                ));
+            }
+            string.split(&*separator).collect()
+        }
+        None => string.split_whitespace().collect(),
+    };
+    Ok(LispValue::list(
+        parts
+            .into_iter()
+            .map(|part| LispValue::String(part.into()))
+            .collect(),
+    ))
+}
+
+fn string_join(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string-join", args, 1, Some(2))?;
+    let separator = match args.get(1) {
+        Some(separator) => string("string-join", separator)?,
+        None => " ".into(),
+    };
+    let parts = list_items("string-join", &args[0])?
+        .iter()
+        .map(|part| string("string-join", part))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(LispValue::String(parts.join(&*separator).into()))
+}
+
+fn string_to_number(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string->number", args, 1, Some(1))?;
+    let string = string("string->number", &args[0])?;
+    Ok(match Number::parse(&string) {
+        Some(num) => LispValue::Number(num),
+        None => LispValue::Boolean(false),
+    })
+}
+
+fn number_to_string(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("number->string", args, 1, Some(1))?;
+    let num = number("number->string", &args[0])?;
+    Ok(LispValue::String(num.to_string().into()))
+}
+
+fn string_to_symbol(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("string->symbol", args, 1, Some(1))?;
+    Ok(LispValue::Symbol(
+        string("string->symbol", &args[0])?.to_string(),
+    ))
+}
+
+fn symbol_to_string(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("symbol->string", args, 1, Some(1))?;
+    match &args[0] {
+        LispValue::Symbol(sym) => Ok(LispValue::String(sym.as_str().into())),
+        value => Err(LispError::type_error("symbol->string", "symbol", value)),
+    }
+}
@@ -0,0 +1,102 @@
+use super::{check_arity, Builtin};
+use crate::error::LispError;
+use crate::number::Number;
+use crate::{Environment, LispValue};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+pub const BUILTINS: &[(&str, Builtin)] = &[
+    ("command-line", command_line),
+    ("exit", exit),
+    ("load", load_builtin),
+];
+
+thread_local! {
+    static COMMAND_LINE: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static GLOBAL_ENVIRONMENT: RefCell<Option<Environment>> = const { RefCell::new(None) };
+    /// The files being loaded, innermost last, used to resolve relative
+    /// paths and to catch files that load themselves.
+    static LOADING: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records the environment that `load` evaluates files in.
+pub fn set_global_environment(env: &Environment) {
+    GLOBAL_ENVIRONMENT.with(|global| *global.borrow_mut() = Some(env.clone()));
+}
+
+/// The environment top-level code runs in, for builtins that evaluate code.
+pub fn global_environment(name: &str) -> Result<Environment, LispError> {
+    GLOBAL_ENVIRONMENT
+        .with(|global| global.borrow().clone())
+        .ok_or_else(|| LispError::runtime(format!("{}: no global environment", name)))
+}
+
+/// Records the program name and arguments returned by `(command-line)`.
+pub fn set_command_line(args: Vec<String>) {
+    COMMAND_LINE.with(|command_line| *command_line.borrow_mut() = args);
+}
+
+fn command_line(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("command-line", args, 0, Some(0))?;
+    let args = COMMAND_LINE.with(|command_line| command_line.borrow().clone());
+    Ok(LispValue::list(
+        args.into_iter()
+            .map(|arg| LispValue::String(arg.into()))
+            .collect(),
+    ))
+}
+
+/// `(exit)` and `(exit #t)` succeed, `(exit #f)` fails, and an integer is
+/// used as the status code.
+fn exit(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("exit", args, 0, Some(1))?;
+    let code = match args.first() {
+        None | Some(LispValue::Boolean(true)) => 0,
+        Some(LispValue::Boolean(false)) => 1,
+        Some(LispValue::Number(Number::Integer(code))) => *code as i32,
+        Some(other) => return Err(LispError::type_error("exit", "boolean or integer", other)),
+    };
+    io::stdout().flush().ok();
+    process::exit(code)
+}
+
+/// Evaluates every form of the file at `path` in `env`. Relative paths are
+/// resolved against the directory of the file currently being loaded.
+pub fn load(path: &str, env: &Environment) -> Result<LispValue, LispError> {
+    let base = LOADING.with(|loading| {
+        let loading = loading.borrow();
+        loading
+            .last()
+            .and_then(|file| file.parent().map(Path::to_path_buf))
+    });
+    let path = match base {
+        Some(base) => base.join(path),
+        None => PathBuf::from(path),
+    };
+    let source = fs::read_to_string(&path)
+        .map_err(|err| LispError::runtime(format!("cannot read {}: {}", path.display(), err)))?;
+    let name = path.display().to_string();
+    let canonical = fs::canonicalize(&path).unwrap_or(path);
+    if LOADING.with(|loading| loading.borrow().contains(&canonical)) {
+        return Err(LispError::runtime(format!(
+            "circular load of {}",
+            canonical.display()
+        )));
+    }
+    LOADING.with(|loading| loading.borrow_mut().push(canonical));
+    let result = crate::run(&name, &source, env);
+    LOADING.with(|loading| loading.borrow_mut().pop());
+    result
+}
+
+fn load_builtin(args: &[LispValue]) -> Result<LispValue, LispError> {
+    check_arity("load", args, 1, Some(1))?;
+    let path = match &args[0] {
+        LispValue::String(path) => path,
+        other => return Err(LispError::type_error("load", "string", other)),
+    };
+    load(path, &global_environment("load")?)
+}
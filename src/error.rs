@@ -0,0 +1,321 @@
+use crate::lexer::Span;
+use crate::stack::{CallFrame, Continuation};
+use crate::{LispExpression, LispValue};
+use std::fmt;
+use std::rc::Rc;
+
+/// Where an error happened: the code it points at, the form that was being
+/// evaluated and the calls in progress, most recent first. Any of them may
+/// be unknown.
+#[derive(Debug, Clone, Default)]
+pub struct Location {
+    pub span: Option<Span>,
+    pub form: Option<LispExpression>,
+    pub backtrace: Option<Vec<CallFrame>>,
+}
+
+/// An error raised while reading or evaluating code. The location is boxed
+/// to keep `Result`s small.
+#[derive(Debug, Clone)]
+pub enum LispError {
+    UnboundVariable {
+        name: String,
+        location: Box<Location>,
+    },
+    /// A procedure was called with the wrong number of arguments.
+    Arity {
+        procedure: String,
+        expected: String,
+        got: usize,
+        location: Box<Location>,
+    },
+    /// A procedure was given an argument of the wrong type.
+    Type {
+        procedure: String,
+        expected: String,
+        got: LispValue,
+        location: Box<Location>,
+    },
+    /// Malformed source code or special form.
+    Syntax {
+        message: String,
+        location: Box<Location>,
+    },
+    /// A value passed to `raise`, or to `raise-continuable` when
+    /// `continuable` is set, that nothing handled.
+    Raise {
+        value: LispValue,
+        continuable: bool,
+        location: Box<Location>,
+    },
+    /// Any other failure, such as division by zero, a bad index or a call
+    /// to `error`, which supplies the irritants.
+    Runtime {
+        message: String,
+        irritants: Vec<LispValue>,
+        location: Box<Location>,
+    },
+    /// Not a failure: a continuation called inside a nested run of the
+    /// evaluator, on its way out to the run it belongs to. Exception handlers
+    /// never see it.
+    Escape {
+        run: usize,
+        kont: Continuation,
+        value: LispValue,
+        location: Box<Location>,
+    },
+}
+
+impl LispError {
+    pub fn unbound(name: &str) -> LispError {
+        LispError::UnboundVariable {
+            name: name.to_string(),
+            location: Box::default(),
+        }
+    }
+
+    pub fn arity(procedure: &str, expected: String, got: usize) -> LispError {
+        LispError::Arity {
+            procedure: procedure.to_string(),
+            expected,
+            got,
+            location: Box::default(),
+        }
+    }
+
+    pub fn type_error(procedure: &str, expected: &str, got: &LispValue) -> LispError {
+        LispError::Type {
+            procedure: procedure.to_string(),
+            expected: expected.to_string(),
+            got: got.clone(),
+            location: Box::default(),
+        }
+    }
+
+    pub fn syntax(message: impl Into<String>) -> LispError {
+        LispError::Syntax {
+            message: message.into(),
+            location: Box::default(),
+        }
+    }
+
+    pub fn raise(value: LispValue) -> LispError {
+        LispError::Raise {
+            value,
+            continuable: false,
+            location: Box::default(),
+        }
+    }
+
+    pub fn raise_continuable(value: LispValue) -> LispError {
+        LispError::Raise {
+            value,
+            continuable: true,
+            location: Box::default(),
+        }
+    }
+
+    pub fn runtime(message: impl Into<String>) -> LispError {
+        LispError::Runtime {
+            message: message.into(),
+            irritants: Vec::new(),
+            location: Box::default(),
+        }
+    }
+
+    pub fn escape(run: usize, kont: Continuation, value: LispValue) -> LispError {
+        LispError::Escape {
+            run,
+            kont,
+            value,
+            location: Box::default(),
+        }
+    }
+
+    /// The error raised by `(error message irritant ...)`.
+    pub fn user(message: impl Into<String>, irritants: Vec<LispValue>) -> LispError {
+        LispError::Runtime {
+            message: message.into(),
+            irritants,
+            location: Box::default(),
+        }
+    }
+
+    pub fn location(&self) -> &Location {
+        match self {
+            LispError::UnboundVariable { location, .. }
+            | LispError::Arity { location, .. }
+            | LispError::Type { location, .. }
+            | LispError::Syntax { location, .. }
+            | LispError::Raise { location, .. }
+            | LispError::Runtime { location, .. }
+            | LispError::Escape { location, .. } => location,
+        }
+    }
+
+    fn location_mut(&mut self) -> &mut Location {
+        match self {
+            LispError::UnboundVariable { location, .. }
+            | LispError::Arity { location, .. }
+            | LispError::Type { location, .. }
+            | LispError::Syntax { location, .. }
+            | LispError::Raise { location, .. }
+            | LispError::Runtime { location, .. }
+            | LispError::Escape { location, .. } => location,
+        }
+    }
+
+    /// Points the error at `span` unless it already points somewhere.
+    pub fn with_span(mut self, span: &Span) -> LispError {
+        self.location_mut().span.get_or_insert_with(|| span.clone());
+        self
+    }
+
+    /// Records `form` as the offending form. Errors are annotated on the way
+    /// out of `eval`, so the innermost form wins.
+    pub fn in_form(mut self, form: &LispExpression) -> LispError {
+        let location = self.location_mut();
+        if location.span.is_none() {
+            location.span = form.span().cloned();
+        }
+        location.form.get_or_insert_with(|| form.clone());
+        self
+    }
+
+    /// Records the calls `backtrace` returns as the error's backtrace, unless
+    /// it already has one from further in.
+    pub fn with_backtrace(mut self, backtrace: impl FnOnce() -> Vec<CallFrame>) -> LispError {
+        self.location_mut().backtrace.get_or_insert_with(backtrace);
+        self
+    }
+
+    pub fn backtrace(&self) -> &[CallFrame] {
+        self.location().backtrace.as_deref().unwrap_or_default()
+    }
+
+    /// The value an exception handler receives: whatever was passed to
+    /// `raise`, or an error object wrapping any other error.
+    pub fn condition(&self) -> LispValue {
+        match self {
+            LispError::Raise { value, .. } => value.clone(),
+            _ => LispValue::Error(Rc::new(self.clone())),
+        }
+    }
+
+    /// Whether an exception handler may return to the point of the raise.
+    pub fn is_continuable(&self) -> bool {
+        matches!(
+            self,
+            LispError::Raise {
+                continuable: true,
+                ..
+            }
+        )
+    }
+
+    /// The message of the error as an error object, without irritants.
+    pub fn message(&self) -> String {
+        match self {
+            LispError::Syntax { message, .. } | LispError::Runtime { message, .. } => {
+                message.clone()
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    pub fn irritants(&self) -> &[LispValue] {
+        match self {
+            LispError::Runtime { irritants, .. } => irritants,
+            _ => &[],
+        }
+    }
+
+    /// Formats the error for the user, quoting the offending line of source
+    /// with a caret under the code it points at.
+    pub fn render(&self) -> String {
+        let mut out = format!("Error: {}", self);
+        let location = self.location();
+        if let Some(span) = &location.span {
+            out.push_str(&format!("\n --> {}", span));
+            if let Some(line) = span.source.text.lines().nth(span.line - 1) {
+                let width = if span.end_line == span.line {
+                    span.end_column.saturating_sub(span.column)
+                } else {
+                    line.chars().count() + 1 - span.column
+                };
+                let number = span.line.to_string();
+                let gutter = " ".repeat(number.len());
+                out.push_str(&format!(
+                    "\n{} |\n{} | {}\n{} | {}{}",
+                    gutter,
+                    number,
+                    line,
+                    gutter,
+                    " ".repeat(span.column - 1),
+                    "^".repeat(width.max(1))
+                ));
+            }
+        } else if let Some(form) = &location.form {
+            out.push_str(&format!("\n in: {}", form));
+        }
+        let backtrace = self.backtrace();
+        if !backtrace.is_empty() {
+            out.push_str("\nBacktrace (most recent call first):");
+            for frame in backtrace {
+                out.push_str(&format!("\n  {}", frame));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for LispError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LispError::UnboundVariable { name, .. } => write!(f, "unbound variable: {}", name),
+            LispError::Arity {
+                procedure,
+                expected,
+                got,
+                ..
+            } => write!(
+                f,
+                "{}: expected {} argument(s), got {}",
+                procedure, expected, got
+            ),
+            LispError::Type {
+                procedure,
+                expected,
+                got,
+                ..
+            } => write!(f, "{}: expected {}, got {}", procedure, expected, got),
+            LispError::Syntax { message, .. } => write!(f, "{}", message),
+            LispError::Runtime {
+                message, irritants, ..
+            } => {
+                write!(f, "{}", message)?;
+                for irritant in irritants {
+                    write!(f, " {}", irritant)?;
+                }
+                Ok(())
+            }
+            LispError::Raise {
+                value: LispValue::String(message),
+                ..
+            } => write!(f, "{}", message),
+            LispError::Raise { value, .. } => write!(f, "uncaught exception: {}", value),
+            LispError::Escape { .. } => write!(
+                f,
+                "continuation called outside the evaluation it belongs to"
+            ),
+        }
+    }
+}
+
+/// Failures reported as plain messages, such as those from `Number`, are
+/// runtime errors.
+impl From<String> for LispError {
+    fn from(message: String) -> LispError {
+        LispError::runtime(message)
+    }
+}
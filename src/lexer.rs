@@ -0,0 +1,447 @@
+use crate::error::LispError;
+use std::fmt;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    LeftParen,
+    RightParen,
+    Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
+    /// `#'`, short for `(syntax ...)`.
+    Syntax,
+    String(String),
+    Atom(String),
+}
+
+/// A named piece of source text, shared by the spans that point into it.
+#[derive(Debug)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+}
+
+/// A region of a source from `line:column` up to, but not including,
+/// `end_line:end_column`. Lines and columns count from 1.
+#[derive(Clone)]
+pub struct Span {
+    pub source: Rc<Source>,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// The span from the start of `self` to the end of `end`.
+    pub fn to(&self, end: &Span) -> Span {
+        Span {
+            end_line: end.end_line,
+            end_column: end.end_column,
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.source.name, self.line, self.column)
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::LeftParen => write!(f, "("),
+            TokenKind::RightParen => write!(f, ")"),
+            TokenKind::Quote => write!(f, "'"),
+            TokenKind::Quasiquote => write!(f, "`"),
+            TokenKind::Unquote => write!(f, ","),
+            TokenKind::UnquoteSplicing => write!(f, ",@"),
+            TokenKind::Syntax => write!(f, "#'"),
+            TokenKind::String(string) => write!(f, "{:?}", string),
+            TokenKind::Atom(atom) => write!(f, "{}", atom),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    source: Rc<Source>,
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a Rc<Source>) -> Self {
+        Lexer {
+            source: source.clone(),
+            chars: source.text.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// The span from `line:column` to the current position.
+    fn span_from(&self, line: usize, column: usize) -> Span {
+        Span {
+            source: self.source.clone(),
+            line,
+            column,
+            end_line: self.line,
+            end_column: self.column,
+        }
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.advance();
+            } else if c == ';' {
+                while let Some(c) = self.advance() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Reads the rest of a string literal after its opening quote.
+    fn read_string(&mut self, line: usize, column: usize) -> Result<String, LispError> {
+        let mut string = String::new();
+        loop {
+            let c = match self.advance() {
+                Some(c) => c,
+                None => {
+                    let span = self.span_from(line, column);
+                    return Err(LispError::syntax("Unterminated string").with_span(&span));
+                }
+            };
+            match c {
+                '"' => return Ok(string),
+                '\\' => string.push(self.read_escape()?),
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn read_escape(&mut self) -> Result<char, LispError> {
+        // The span starts at the backslash, which has already been read.
+        let (line, column) = (self.line, self.column - 1);
+        let error = |lexer: &Self, message: String| {
+            LispError::syntax(message).with_span(&lexer.span_from(line, column))
+        };
+        match self.advance() {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('a') => Ok('\u{7}'),
+            Some('b') => Ok('\u{8}'),
+            Some(c @ ('"' | '\\' | '|')) => Ok(c),
+            Some('x') => {
+                let mut hex = String::new();
+                loop {
+                    match self.advance() {
+                        Some(';') => break,
+                        Some(c) if c.is_ascii_hexdigit() => hex.push(c),
+                        _ => return Err(error(self, "Invalid \\x escape".to_string())),
+                    }
+                }
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| error(self, "Invalid \\x escape".to_string()))
+            }
+            Some(c) => Err(error(self, format!("Unknown escape '\\{}'", c))),
+            None => Err(error(self, "Unexpected end of input in string".to_string())),
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, LispError> {
+        self.skip_whitespace_and_comments();
+        let (line, column) = (self.line, self.column);
+        let c = match self.advance() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        let kind = match c {
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            '\'' => TokenKind::Quote,
+            '`' => TokenKind::Quasiquote,
+            ',' => {
+                if self.chars.peek() == Some(&'@') {
+                    self.advance();
+                    TokenKind::UnquoteSplicing
+                } else {
+                    TokenKind::Unquote
+                }
+            }
+            '"' => TokenKind::String(self.read_string(line, column)?),
+            '#' if self.chars.peek() == Some(&'\'') => {
+                self.advance();
+                TokenKind::Syntax
+            }
+            c => {
+                let mut atom = c.to_string();
+                // In a character literal such as `#\(` the first character
+                // after the backslash is never a delimiter.
+                if c == '#' && self.chars.peek() == Some(&'\\') {
+                    atom.extend(self.advance());
+                    atom.extend(self.advance());
+                }
+                while let Some(&c) = self.chars.peek() {
+                    if is_delimiter(c) {
+                        break;
+                    }
+                    atom.push(c);
+                    self.advance();
+                }
+                TokenKind::Atom(atom)
+            }
+        };
+        Ok(Some(Token {
+            kind,
+            span: self.span_from(line, column),
+        }))
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '\'' | '`' | ',' | '"' | ';')
+}
+
+pub fn tokenize(source: &Rc<Source>) -> Result<Vec<Token>, LispError> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next_token()? {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// True when `source` stops inside an open list or string literal, so that
+/// the REPL should read another line before parsing it.
+pub fn is_incomplete(text: &str) -> bool {
+    let source = Rc::new(Source {
+        name: String::new(),
+        text: text.to_string(),
+    });
+    let mut lexer = Lexer::new(&source);
+    let mut depth = 0;
+    loop {
+        match lexer.next_token() {
+            Ok(Some(token)) => match token.kind {
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen => depth -= 1,
+                _ => {}
+            },
+            Ok(None) => return depth > 0,
+            // An error at the very end of the input means a string was cut off.
+            Err(_) => return lexer.chars.peek().is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(text: &str) -> Result<Vec<Token>, LispError> {
+        tokenize(&Rc::new(Source {
+            name: "<test>".to_string(),
+            text: text.to_string(),
+        }))
+    }
+
+    fn kinds(text: &str) -> Vec<TokenKind> {
+        lex(text)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    fn atom(atom: &str) -> TokenKind {
+        TokenKind::Atom(atom.to_string())
+    }
+
+    /// The message of the error `text` fails with, and where it was found as
+    /// `line:column`.
+    fn error_at(text: &str) -> (String, String) {
+        let err = lex(text).unwrap_err();
+        let span = err.location().span.as_ref().unwrap();
+        (err.to_string(), format!("{}:{}", span.line, span.column))
+    }
+
+    #[test]
+    fn parentheses_need_no_whitespace() {
+        use TokenKind::{LeftParen, RightParen};
+        assert_eq!(
+            kinds("(define x 1)"),
+            [LeftParen, atom("define"), atom("x"), atom("1"), RightParen]
+        );
+        assert_eq!(
+            kinds("((f)x(g))"),
+            [
+                LeftParen,
+                LeftParen,
+                atom("f"),
+                RightParen,
+                atom("x"),
+                LeftParen,
+                atom("g"),
+                RightParen,
+                RightParen
+            ]
+        );
+    }
+
+    #[test]
+    fn quote_shorthands() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("'a `(b ,c ,@d) #'e"),
+            [
+                Quote,
+                atom("a"),
+                Quasiquote,
+                LeftParen,
+                atom("b"),
+                Unquote,
+                atom("c"),
+                UnquoteSplicing,
+                atom("d"),
+                RightParen,
+                Syntax,
+                atom("e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            kinds(r#""a\x41;b\n\"\\""#),
+            [TokenKind::String("aAb\n\"\\".to_string())]
+        );
+        assert_eq!(kinds(r#""(;)""#), [TokenKind::String("(;)".to_string())]);
+        assert_eq!(
+            error_at(r#"(f "a\q")"#),
+            ("Unknown escape '\\q'".to_string(), "1:6".to_string())
+        );
+        assert_eq!(
+            error_at(r#""\x41""#),
+            ("Invalid \\x escape".to_string(), "1:2".to_string())
+        );
+        assert_eq!(
+            error_at("\n  \"abc"),
+            ("Unterminated string".to_string(), "2:3".to_string())
+        );
+    }
+
+    #[test]
+    fn character_literals_may_be_delimiters() {
+        use TokenKind::{LeftParen, RightParen};
+        assert_eq!(
+            kinds(r"(#\( #\) #\a #\space)"),
+            [
+                LeftParen,
+                atom(r"#\("),
+                atom(r"#\)"),
+                atom(r"#\a"),
+                atom(r"#\space"),
+                RightParen
+            ]
+        );
+    }
+
+    #[test]
+    fn dotted_pairs_and_comments() {
+        use TokenKind::{LeftParen, RightParen};
+        assert_eq!(
+            kinds("; leading\n(a . b) ; trailing (\n(c;inner\nd)"),
+            [
+                LeftParen,
+                atom("a"),
+                atom("."),
+                atom("b"),
+                RightParen,
+                LeftParen,
+                atom("c"),
+                atom("d"),
+                RightParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_record_their_positions() {
+        let spans: Vec<_> = lex("(a\n  bc \"x\ny\")")
+            .unwrap()
+            .into_iter()
+            .map(|token| {
+                (
+                    token.span.line,
+                    token.span.column,
+                    token.span.end_line,
+                    token.span.end_column,
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (1, 1, 1, 2),
+                (1, 2, 1, 3),
+                (2, 3, 2, 5),
+                (2, 6, 3, 3),
+                (3, 3, 3, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_input_waits_for_more_lines() {
+        assert!(is_incomplete("(define (f x)"));
+        assert!(is_incomplete("(display \"a\nb"));
+        assert!(is_incomplete("(f \")\""));
+        assert!(is_incomplete("(f ; )\n"));
+        assert!(is_incomplete(r"(list #\)"));
+        assert!(!is_incomplete("(define (f x) x)"));
+        assert!(!is_incomplete("(f \"(\")"));
+        assert!(!is_incomplete("(f) ; ("));
+        assert!(!is_incomplete(r"(list #\()"));
+        assert!(!is_incomplete("x)"));
+    }
+}
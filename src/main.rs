@@ -1,202 +1,2937 @@
+mod builtins;
+mod error;
+mod lexer;
+mod number;
+mod printer;
+mod stack;
+mod syntax;
+
+use error::LispError;
+use lexer::{Source, Span, Token, TokenKind};
+use number::Number;
+use stack::{CallFrame, Captured, Continuation, Run};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::{self, Write};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::iter::{self, Peekable};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::slice::Iter;
+use std::vec;
+use syntax::{Binding, Bindings, SyntaxObject, SyntaxRules};
+
+type Tokens<'a> = Peekable<Iter<'a, Token>>;
 
 #[derive(Debug, Clone)]
 enum LispExpression {
-    Number(f64),
+    Number(Number),
     Boolean(bool),
-    Symbol(String),
-    List(Vec<LispExpression>),
-    Lambda(Vec<String>, Box<LispExpression>),
+    Char(char),
+    String(Rc<str>),
+    Symbol(Identifier),
+    List(List),
+    /// `(a b . c)`: the listed elements followed by a non-list tail.
+    DottedList(Rc<[LispExpression]>, Box<LispExpression>),
+}
+
+impl LispExpression {
+    /// Where the expression was read, for symbols and lists from source.
+    fn span(&self) -> Option<&Span> {
+        match self {
+            LispExpression::Symbol(identifier) => identifier.span.as_ref(),
+            LispExpression::List(list) => list.span.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Converts data back into source code, the inverse of
+    /// `LispValue::from_datum`, as if it had been read at `span`. Used on the
+    /// code a macro produces.
+    fn from_value(value: &LispValue, span: Option<&Span>) -> Result<LispExpression, LispError> {
+        match value {
+            LispValue::Number(num) => Ok(LispExpression::Number(num.clone())),
+            LispValue::Boolean(b) => Ok(LispExpression::Boolean(*b)),
+            LispValue::Char(c) => Ok(LispExpression::Char(*c)),
+            LispValue::String(string) => Ok(LispExpression::String(string.clone())),
+            LispValue::Symbol(sym) => Ok(LispExpression::Symbol(Identifier {
+                name: sym.clone(),
+                span: span.cloned(),
+                rename: None,
+            })),
+            LispValue::Nil => Ok(LispExpression::List(List {
+                items: Rc::new([]),
+                span: span.cloned(),
+            })),
+            LispValue::Pair(_) => {
+                let mut items = Vec::new();
+                let mut rest = value.clone();
+                while let LispValue::Pair(pair) = rest {
+                    items.push(LispExpression::from_value(&pair.car.borrow(), span)?);
+                    rest = pair.cdr.borrow().clone();
+                }
+                match rest {
+                    LispValue::Nil => Ok(LispExpression::List(List {
+                        items: items.into(),
+                        span: span.cloned(),
+                    })),
+                    tail => Ok(LispExpression::DottedList(
+                        items.into(),
+                        Box::new(LispExpression::from_value(&tail, span)?),
+                    )),
+                }
+            }
+            LispValue::SyntaxObject(syntax) => Ok(syntax.expr.clone()),
+            other => Err(LispError::syntax(format!(
+                "{} cannot be used as code",
+                other
+            ))),
+        }
+    }
+}
+
+/// A symbol in source code. It compares equal to its name and derefs to it.
+#[derive(Debug, Clone)]
+struct Identifier {
+    name: String,
+    span: Option<Span>,
+    /// Set on identifiers a `syntax-rules` template inserted, to keep them
+    /// apart from the user's identifiers of the same name.
+    rename: Option<Rc<Rename>>,
+}
+
+/// How a macro-inserted identifier is resolved. Binding forms bind it under
+/// `key`, which no other identifier shares, so its bindings can't capture
+/// the user's variables. If nothing in the expansion binds it, it means what
+/// `original` meant where the macro was defined, so the user's bindings
+/// can't capture it either.
+#[derive(Debug)]
+struct Rename {
+    key: String,
+    /// The expansion that inserted the identifier.
+    mark: usize,
+    env: Environment,
+    original: Identifier,
+}
+
+impl Identifier {
+    /// The name this identifier is bound under in an environment.
+    fn key(&self) -> &str {
+        match &self.rename {
+            Some(rename) => &rename.key,
+            None => &self.name,
+        }
+    }
+}
+
+impl Deref for Identifier {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl PartialEq<str> for Identifier {
+    fn eq(&self, other: &str) -> bool {
+        self.name == other
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl From<&str> for Identifier {
+    fn from(name: &str) -> Identifier {
+        Identifier {
+            name: name.to_string(),
+            span: None,
+            rename: None,
+        }
+    }
+}
+
+/// A parenthesized form in source code. It derefs to its elements.
+#[derive(Debug, Clone)]
+struct List {
+    items: Rc<[LispExpression]>,
+    span: Option<Span>,
+}
+
+impl Deref for List {
+    type Target = [LispExpression];
+
+    fn deref(&self) -> &[LispExpression] {
+        &self.items
+    }
+}
+
+impl<T: Into<Rc<[LispExpression]>>> From<T> for List {
+    fn from(items: T) -> List {
+        List {
+            items: items.into(),
+            span: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 enum LispValue {
-    Number(f64),
+    Number(Number),
     Boolean(bool),
-    Lambda(Vec<String>, Box<LispExpression>, Environment),
+    Lambda(Rc<Closure>),
+    Char(char),
+    String(Rc<str>),
+    Symbol(String),
+    Builtin(
+        &'static str,
+        fn(&[LispValue]) -> Result<LispValue, LispError>,
+    ),
+    Pair(Rc<Pair>),
+    /// An error object, as caught by an exception handler.
+    Error(Rc<LispError>),
+    /// A transformer from `define-macro`, called on the unevaluated operands
+    /// of forms that use it.
+    Macro(Rc<Closure>),
+    /// A hygienic macro from `define-syntax`.
+    Syntax(Rc<SyntaxRules>),
+    /// A procedural macro from `define-syntax`: called with each use as a
+    /// syntax object, it returns the syntax to evaluate in its place.
+    Transformer(Rc<Closure>),
+    SyntaxObject(Rc<SyntaxObject>),
+    /// What a `syntax-case` or `with-syntax` pattern variable matched. Only
+    /// `syntax` templates can refer to it.
+    PatternVariable(Rc<Binding>),
+    /// A procedure that needs the continuation of its call, such as `call/cc`.
+    Control(&'static str, Control),
+    /// A continuation captured by `call/cc`. Calling it abandons the current
+    /// continuation and returns its argument to this one.
+    Continuation(Captured),
+    /// Several values returned at once by `values`, for `call-with-values`
+    /// to pass on as arguments.
+    Values(Rc<[LispValue]>),
+    Nil,
+    Unspecified,
 }
 
-#[derive(Debug, Clone)]
+/// A mutable cons cell.
+#[derive(Debug)]
+struct Pair {
+    car: RefCell<LispValue>,
+    cdr: RefCell<LispValue>,
+}
+
+/// Unlinks the tail of a list iteratively so that dropping a long list
+/// doesn't recurse once per element.
+impl Drop for Pair {
+    fn drop(&mut self) {
+        let mut rest = self.cdr.replace(LispValue::Nil);
+        while let LispValue::Pair(pair) = rest {
+            match Rc::try_unwrap(pair) {
+                Ok(pair) => rest = pair.cdr.replace(LispValue::Nil),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl LispValue {
+    fn cons(car: LispValue, cdr: LispValue) -> LispValue {
+        LispValue::Pair(Rc::new(Pair {
+            car: RefCell::new(car),
+            cdr: RefCell::new(cdr),
+        }))
+    }
+
+    /// Builds a list of `items` ending in `tail` instead of the empty list.
+    fn list_with_tail(items: Vec<LispValue>, tail: LispValue) -> LispValue {
+        items
+            .into_iter()
+            .rev()
+            .fold(tail, |tail, item| LispValue::cons(item, tail))
+    }
+
+    fn list(items: Vec<LispValue>) -> LispValue {
+        LispValue::list_with_tail(items, LispValue::Nil)
+    }
+
+    /// Returns `values` from one expression: a single value stands alone.
+    fn values(mut values: Vec<LispValue>) -> LispValue {
+        match values.len() {
+            1 => values.pop().unwrap(),
+            _ => LispValue::Values(values.into()),
+        }
+    }
+
+    /// Converts source code into the data it denotes, as `quote` does.
+    fn from_datum(datum: &LispExpression) -> LispValue {
+        match datum {
+            LispExpression::Number(num) => LispValue::Number(num.clone()),
+            LispExpression::Boolean(b) => LispValue::Boolean(*b),
+            LispExpression::Char(c) => LispValue::Char(*c),
+            LispExpression::String(string) => LispValue::String(string.clone()),
+            LispExpression::Symbol(sym) => LispValue::Symbol(sym.to_string()),
+            LispExpression::List(items) => {
+                LispValue::list(items.iter().map(LispValue::from_datum).collect())
+            }
+            LispExpression::DottedList(items, tail) => LispValue::list_with_tail(
+                items.iter().map(LispValue::from_datum).collect(),
+                LispValue::from_datum(tail),
+            ),
+        }
+    }
+
+    /// Identity for pairs and procedures, value equality for atoms.
+    fn eqv(&self, other: &LispValue) -> bool {
+        match (self, other) {
+            (LispValue::Number(a), LispValue::Number(b)) => a.eqv(b),
+            (LispValue::Boolean(a), LispValue::Boolean(b)) => a == b,
+            (LispValue::Char(a), LispValue::Char(b)) => a == b,
+            (LispValue::String(a), LispValue::String(b)) => Rc::ptr_eq(a, b),
+            (LispValue::Symbol(a), LispValue::Symbol(b)) => a == b,
+            (LispValue::Builtin(a, _), LispValue::Builtin(b, _)) => a == b,
+            (LispValue::Control(a, _), LispValue::Control(b, _)) => a == b,
+            (LispValue::Continuation(a), LispValue::Continuation(b)) => a.kont.same(&b.kont),
+            (LispValue::Lambda(a), LispValue::Lambda(b)) => Rc::ptr_eq(a, b),
+            (LispValue::Pair(a), LispValue::Pair(b)) => Rc::ptr_eq(a, b),
+            (LispValue::Error(a), LispValue::Error(b)) => Rc::ptr_eq(a, b),
+            (LispValue::Macro(a), LispValue::Macro(b)) => Rc::ptr_eq(a, b),
+            (LispValue::Syntax(a), LispValue::Syntax(b)) => Rc::ptr_eq(a, b),
+            (LispValue::Transformer(a), LispValue::Transformer(b)) => Rc::ptr_eq(a, b),
+            (LispValue::SyntaxObject(a), LispValue::SyntaxObject(b)) => Rc::ptr_eq(a, b),
+            (LispValue::Nil, LispValue::Nil) => true,
+            (LispValue::Unspecified, LispValue::Unspecified) => true,
+            _ => false,
+        }
+    }
+
+    /// Structural equality: pairs are compared element by element and strings
+    /// by content. Only the cars are compared recursively, so long lists
+    /// don't recurse once per element.
+    fn equal(&self, other: &LispValue) -> bool {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        loop {
+            match (&a, &b) {
+                (LispValue::String(x), LispValue::String(y)) => return x == y,
+                (LispValue::Pair(x), LispValue::Pair(y)) => {
+                    if Rc::ptr_eq(x, y) {
+                        return true;
+                    }
+                    if !x.car.borrow().equal(&y.car.borrow()) {
+                        return false;
+                    }
+                    let next = (x.cdr.borrow().clone(), y.cdr.borrow().clone());
+                    (a, b) = next;
+                }
+                _ => return a.eqv(&b),
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Closure {
+    /// The name the procedure was defined under, for printing.
+    name: Option<String>,
+    params: Vec<String>,
+    /// Receives the arguments beyond `params` as a list, if present.
+    rest: Option<String>,
+    body: Rc<[LispExpression]>,
+    env: Environment,
+}
+
+/// A lexical scope. Frames are shared, so closures capture their defining
+/// scope by reference and see later definitions made in it.
+#[derive(Clone)]
 struct Environment {
+    frame: Rc<RefCell<Frame>>,
+}
+
+struct Frame {
     bindings: HashMap<String, LispValue>,
+    parent: Option<Environment>,
+}
+
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Environment").finish_non_exhaustive()
+    }
 }
 
 impl Environment {
     fn new() -> Self {
         Environment {
-            bindings: HashMap::new(),
+            frame: Rc::new(RefCell::new(Frame {
+                bindings: HashMap::new(),
+                parent: None,
+            })),
         }
     }
 
-    fn extend(&mut self, bindings: Vec<(String, LispValue)>) {
-        self.bindings.extend(bindings.into_iter());
+    fn standard() -> Self {
+        let env = Environment::new();
+        builtins::install(&env);
+        env
+    }
+
+    /// Creates a child scope of `self` holding `bindings`.
+    fn extend(&self, bindings: Vec<(String, LispValue)>) -> Environment {
+        Environment {
+            frame: Rc::new(RefCell::new(Frame {
+                bindings: bindings.into_iter().collect(),
+                parent: Some(self.clone()),
+            })),
+        }
     }
 
-    fn get(&self, key: &str) -> Option<&LispValue> {
-        self.bindings.get(key)
+    fn get(&self, key: &str) -> Option<LispValue> {
+        let frame = self.frame.borrow();
+        match frame.bindings.get(key) {
+            Some(value) => Some(value.clone()),
+            None => frame.parent.as_ref()?.get(key),
+        }
     }
 
-    fn set(&mut self, key: String, value: LispValue) {
-        self.bindings.insert(key, value);
+    /// Looks up what `ident` refers to, following macro renames.
+    fn lookup(&self, ident: &Identifier) -> Option<LispValue> {
+        match (self.get(ident.key()), &ident.rename) {
+            (None, Some(rename)) => rename.env.lookup(&rename.original),
+            (value, _) => value,
+        }
     }
-}
 
-fn eval(expr: &LispExpression, env: &mut Environment) -> Result<LispValue, String> {
-    match expr {
-        LispExpression::Number(num) => Ok(LispValue::Number(*num)),
-        LispExpression::Boolean(b) => Ok(LispValue::Boolean(*b)),
-        LispExpression::Symbol(sym) => {
-            match env.get(sym) {
-                Some(value) => Ok(value.clone()),
-                None => Err(format!("Undefined symbol: {}", sym)),
+    /// Binds `key` in this frame, shadowing any outer binding.
+    fn define(&self, key: String, value: LispValue) {
+        self.frame.borrow_mut().bindings.insert(key, value);
+    }
+
+    /// Updates the nearest existing binding of `key`.
+    fn set(&self, key: &str, value: LispValue) -> Result<(), LispError> {
+        let mut frame = self.frame.borrow_mut();
+        if let Some(slot) = frame.bindings.get_mut(key) {
+            *slot = value;
+            return Ok(());
+        }
+        match &frame.parent {
+            Some(parent) => parent.set(key, value),
+            None => Err(LispError::unbound(key)),
+        }
+    }
+
+    /// Updates the binding `ident` refers to, following macro renames.
+    fn assign(&self, ident: &Identifier, value: LispValue) -> Result<(), LispError> {
+        match &ident.rename {
+            Some(rename) if self.get(ident.key()).is_none() => {
+                rename.env.assign(&rename.original, value)
             }
+            _ => self.set(ident.key(), value),
+        }
+    }
+}
+
+/// The outcome of evaluating one step of a form: a value for whatever is
+/// waiting on it, an expression to evaluate next, a procedure call, or a
+/// step to carry out with part of the form left pending until it finishes.
+/// Nothing waits on the Rust stack, so the rest of a computation can be
+/// captured as a continuation and recursion is limited only by memory.
+enum Step {
+    Value(LispValue),
+    Eval(LispExpression, Environment),
+    Call(LispValue, Vec<LispValue>),
+    Push(Pending, Box<Step>),
+}
+
+/// Evaluates `expr` with `pending` waiting for its value.
+fn then(pending: Pending, expr: &LispExpression, env: &Environment) -> Step {
+    Step::Push(pending, Box::new(Step::Eval(expr.clone(), env.clone())))
+}
+
+/// The part of a form left to do once the subexpression it is waiting on
+/// has a value. A continuation is a stack of these.
+#[derive(Debug, Clone)]
+enum Pending {
+    /// The rest of a body, from `body[index]` on.
+    Sequence {
+        body: Rc<[LispExpression]>,
+        index: usize,
+        env: Environment,
+    },
+    /// The operator of a combination, which may turn out to be a macro.
+    Operator {
+        form: List,
+        env: Environment,
+    },
+    /// The arguments of a call to `func`, from `exprs[index]` on.
+    Arguments {
+        func: LispValue,
+        exprs: Rc<[LispExpression]>,
+        index: usize,
+        values: Vec<LispValue>,
+        form: List,
+        env: Environment,
+    },
+    /// The unquoted expressions of a quasiquote; `values` holds those
+    /// evaluated so far.
+    Quasiquote {
+        form: List,
+        exprs: Rc<[LispExpression]>,
+        values: Vec<LispValue>,
+        env: Environment,
+    },
+    /// The input of a `syntax-case`.
+    SyntaxCase {
+        form: List,
+        env: Environment,
+    },
+    /// The fender of the `syntax-case` clause at `form[index]`, which
+    /// matched `input` and bound its pattern variables in `clause_env`.
+    Fender {
+        form: List,
+        input: LispValue,
+        index: usize,
+        env: Environment,
+        clause_env: Environment,
+    },
+    /// The expression of the `with-syntax` spec at `index`.
+    WithSyntax {
+        form: List,
+        index: usize,
+        bindings: Bindings,
+        env: Environment,
+    },
+    Define {
+        name: Identifier,
+        env: Environment,
+    },
+    Set {
+        name: Identifier,
+        form: List,
+        env: Environment,
+    },
+    If {
+        form: List,
+        env: Environment,
+    },
+    /// The test of `when`, or of `unless` when `expected` is false.
+    When {
+        form: List,
+        env: Environment,
+        expected: bool,
+    },
+    /// The inits of a `let`; `values` holds those evaluated so far.
+    Let {
+        bindings: LetBindings,
+        values: Vec<LispValue>,
+        form: List,
+        env: Environment,
+    },
+    /// The init of `bindings[index]` in a `let*`, with `env` binding the
+    /// variables before it.
+    LetStar {
+        bindings: LetBindings,
+        index: usize,
+        form: List,
+        env: Environment,
+    },
+    /// The init of `bindings[index]` in a `letrec` or `letrec*`.
+    Letrec {
+        bindings: LetBindings,
+        index: usize,
+        values: Vec<LispValue>,
+        form: List,
+        env: Environment,
+        sequential: bool,
+    },
+    /// The test of the clause at `clauses[index]`. The clauses of a `guard`
+    /// carry the error to raise again if none of them applies.
+    Cond {
+        clauses: Rc<[LispExpression]>,
+        index: usize,
+        env: Environment,
+        form: List,
+        reraise: Option<Rc<LispError>>,
+    },
+    /// The receiver of a `(test => receiver)` clause whose test gave `value`.
+    Receiver {
+        value: LispValue,
+        form: List,
+    },
+    /// The key of a `case`.
+    Case {
+        form: List,
+        env: Environment,
+    },
+    /// The operands of `and`, from `form[index]` on.
+    And {
+        form: List,
+        index: usize,
+        env: Environment,
+    },
+    /// The operands of `or`, from `form[index]` on.
+    Or {
+        form: List,
+        index: usize,
+        env: Environment,
+    },
+    /// The body of a `guard`, whose clauses handle what it raises.
+    Guard {
+        var: Identifier,
+        clauses: List,
+        env: Environment,
+    },
+    /// The thunk of `with-exception-handler`.
+    Handler {
+        handler: LispValue,
+    },
+    /// The clauses of a `guard` at work, with the calls that were in
+    /// progress when the exception was raised.
+    Handling {
+        backtrace: Rc<[CallFrame]>,
+    },
+    /// An exception handler called for `err`. `outer` holds the handlers in
+    /// effect while it runs: those outside the one that was called.
+    Raised {
+        err: Rc<LispError>,
+        outer: Continuation,
+    },
+    /// The producer of `call-with-values`, whose values go to `consumer`.
+    Values {
+        consumer: LispValue,
+    },
+    /// A procedure call in progress, kept for backtraces.
+    Call(CallFrame),
+}
+
+type LetBindings = Rc<[(String, LispExpression)]>;
+
+impl Pending {
+    /// The form this is part of, for locating errors and calls.
+    fn form(&self) -> Option<&List> {
+        match self {
+            Pending::Operator { form, .. }
+            | Pending::Arguments { form, .. }
+            | Pending::Quasiquote { form, .. }
+            | Pending::SyntaxCase { form, .. }
+            | Pending::Fender { form, .. }
+            | Pending::WithSyntax { form, .. }
+            | Pending::Set { form, .. }
+            | Pending::If { form, .. }
+            | Pending::When { form, .. }
+            | Pending::Cond { form, .. }
+            | Pending::Receiver { form, .. }
+            | Pending::Case { form, .. }
+            | Pending::And { form, .. }
+            | Pending::Or { form, .. } => Some(form),
+            _ => None,
         }
-        LispExpression::List(list) => {
-            if list.is_empty() {
-                return Err("Empty list".to_string());
+    }
+
+    /// Carries on now that the awaited subexpression produced `value`.
+    fn resume(self, value: LispValue) -> Result<Step, LispError> {
+        match self {
+            Pending::Sequence { body, index, env } => Ok(eval_body(body, index, &env)),
+            Pending::Operator { form, env } => eval_operands(value, form, &env),
+            Pending::Arguments {
+                func,
+                exprs,
+                index,
+                mut values,
+                form,
+                env,
+            } => {
+                values.push(value);
+                eval_arguments(func, exprs, index, values, form, &env)
             }
-            match &list[0] {
-                LispExpression::Symbol(s) if s == "define" => {
-                    if list.len() != 3 {
-                        return Err("Invalid define expression".to_string());
-                    }
-                    if let LispExpression::Symbol(name) = &list[1] {
-                        let value = eval(&list[2], env)?;
-                        env.set(name.clone(), value.clone());
-                        Ok(value)
-                    } else {
-                        Err("Invalid variable name in define".to_string())
-                    }
+            Pending::Quasiquote {
+                form,
+                exprs,
+                mut values,
+                env,
+            } => {
+                values.push(value);
+                eval_quasiquote(form, exprs, values, &env)
+            }
+            Pending::SyntaxCase { form, env } => eval_syntax_clauses(form, value, 3, &env),
+            Pending::Fender {
+                form,
+                input,
+                index,
+                env,
+                clause_env,
+            } => match &form[index] {
+                LispExpression::List(clause) if is_truthy(&value) => {
+                    Ok(Step::Eval(clause[2].clone(), clause_env))
                 }
-                LispExpression::Symbol(s) if s == "lambda" => {
-                    if list.len() != 3 {
-                        return Err("Invalid lambda expression".to_string());
-                    }
-                    if let LispExpression::List(params) = &list[1] {
-                        let mut param_names = Vec::new();
-                        for param in params {
-                            if let LispExpression::Symbol(name) = param {
-                                param_names.push(name.clone());
-                            } else {
-                                return Err("Invalid parameter name in lambda".to_string());
-                            }
-                        }
-                        let body = Box::new(list[2].clone());
-                        Ok(LispValue::Lambda(
-                            param_names,
-                            body,
-                            env.clone(), // Capture the current environment
-                        ))
-                    } else {
-                        Err("Invalid parameter list in lambda".to_string())
-                    }
+                _ => eval_syntax_clauses(form, input, index + 1, &env),
+            },
+            Pending::WithSyntax {
+                form,
+                index,
+                bindings,
+                env,
+            } => bind_with_syntax(form, index, bindings, value, &env),
+            Pending::Define { name, env } => {
+                env.define(name.key().to_string(), value.clone());
+                Ok(Step::Value(value))
+            }
+            Pending::Set { name, env, .. } => {
+                env.assign(&name, value)?;
+                Ok(Step::Value(LispValue::Unspecified))
+            }
+            Pending::If { form, env } => Ok(if is_truthy(&value) {
+                Step::Eval(form[2].clone(), env)
+            } else if form.len() == 4 {
+                Step::Eval(form[3].clone(), env)
+            } else {
+                Step::Value(LispValue::Unspecified)
+            }),
+            Pending::When {
+                form,
+                env,
+                expected,
+            } => Ok(if is_truthy(&value) == expected {
+                eval_body(form.items.clone(), 2, &env)
+            } else {
+                Step::Value(LispValue::Unspecified)
+            }),
+            Pending::Let {
+                bindings,
+                mut values,
+                form,
+                env,
+            } => {
+                values.push(value);
+                Ok(eval_let_inits(bindings, values, form, env))
+            }
+            Pending::LetStar {
+                bindings,
+                index,
+                form,
+                env,
+            } => {
+                let scope = env.extend(vec![(bindings[index].0.clone(), value)]);
+                Ok(eval_let_star_inits(bindings, index + 1, form, scope))
+            }
+            Pending::Letrec {
+                bindings,
+                index,
+                mut values,
+                form,
+                env,
+                sequential,
+            } => {
+                if sequential {
+                    env.define(bindings[index].0.clone(), value);
+                } else {
+                    values.push(value);
                 }
-                _ => Err("Invalid expression".to_string()),
+                Ok(eval_letrec_inits(
+                    bindings,
+                    index + 1,
+                    values,
+                    form,
+                    env,
+                    sequential,
+                ))
+            }
+            Pending::Cond {
+                clauses,
+                index,
+                env,
+                form,
+                reraise,
+            } => match &clauses[index] {
+                LispExpression::List(clause) if is_truthy(&value) => {
+                    eval_clause_body(clause, value, &env, form)
+                }
+                _ => eval_clauses(clauses, index + 1, env, form, reraise),
+            },
+            Pending::Receiver { value: arg, .. } => Ok(Step::Call(value, vec![arg])),
+            Pending::Values { consumer } => match value {
+                LispValue::Values(values) => Ok(Step::Call(consumer, values.to_vec())),
+                value => Ok(Step::Call(consumer, vec![value])),
+            },
+            Pending::Case { form, env } => eval_case_clauses(form, value, &env),
+            Pending::And { form, index, env } if is_truthy(&value) => {
+                Ok(eval_and(form, index, &env))
+            }
+            Pending::Or { form, index, env } if !is_truthy(&value) => {
+                Ok(eval_or(form, index, &env))
+            }
+            Pending::And { .. }
+            | Pending::Or { .. }
+            | Pending::Guard { .. }
+            | Pending::Handler { .. }
+            | Pending::Handling { .. }
+            | Pending::Call(_) => Ok(Step::Value(value)),
+            Pending::Raised { err, .. } if err.is_continuable() => Ok(Step::Value(value)),
+            Pending::Raised { err, .. } => {
+                let secondary = LispError::user(
+                    "exception handler returned from a non-continuable exception",
+                    vec![err.condition()],
+                );
+                let secondary = match &err.location().span {
+                    Some(span) => secondary.with_span(span),
+                    None => secondary,
+                };
+                Err(secondary.with_backtrace(|| err.backtrace().to_vec()))
             }
         }
-        LispExpression::Lambda(_, _) => Err("Lambda cannot be evaluated directly".to_string()),
     }
 }
-fn apply(func: &LispValue, args: &[LispExpression], env: &mut Environment) -> Result<LispValue, String> {
-    match func {
-        LispValue::Lambda(params, body, closure) => {
-            if args.len() != params.len() {
-                return Err("Incorrect number of arguments".to_string());
+
+/// Procedures that work on the continuation of their call, which builtins
+/// can't see.
+#[derive(Debug, Clone, Copy)]
+enum Control {
+    CallWithCurrentContinuation,
+    Values,
+    CallWithValues,
+    WithExceptionHandler,
+    Backtrace,
+}
+
+const CONTROLS: &[(&str, Control)] = &[
+    (
+        "call-with-current-continuation",
+        Control::CallWithCurrentContinuation,
+    ),
+    ("call/cc", Control::CallWithCurrentContinuation),
+    ("values", Control::Values),
+    ("call-with-values", Control::CallWithValues),
+    ("with-exception-handler", Control::WithExceptionHandler),
+    ("backtrace", Control::Backtrace),
+];
+
+impl Control {
+    fn call(
+        self,
+        name: &str,
+        args: Vec<LispValue>,
+        kont: &mut Continuation,
+    ) -> Result<Step, LispError> {
+        match self {
+            // `(call/cc proc)` calls `proc` with the continuation of the
+            // call, as a procedure that abandons whatever continuation it is
+            // called in and returns its argument from this one instead.
+            Control::CallWithCurrentContinuation => {
+                builtins::check_arity(name, &args, 1, Some(1))?;
+                let captured = Captured::new(kont.clone());
+                Ok(Step::Call(
+                    args[0].clone(),
+                    vec![LispValue::Continuation(captured)],
+                ))
             }
-            let mut new_env = closure.clone();
-            for (param, arg) in params.iter().zip(args) {
-                if let LispExpression::Symbol(name) = *param {
-                    let value = eval(arg, env)?;
-                    new_env.set(name.clone(), value);
-                    println!("O símbolo é: {}", name);
-                } else {
-                    match arg {
-                        LispExpression::Number(num) => println!("O parâmetro é um número: {}", num),
-                        LispExpression::Boolean(b) => println!("O parâmetro é um booleano: {}", b),
-                        LispExpression::List(_) => println!("O parâmetro é uma lista"),
-                        LispExpression::Lambda(_, _) => println!("O parâmetro é uma lambda"),
-                        _ => println!("Tipo de parâmetro desconhecido"),
+            // `(values obj ...)` returns its arguments to the continuation of
+            // the call; a single value is returned as it is.
+            Control::Values => Ok(Step::Value(LispValue::values(args))),
+            // `(call-with-values producer consumer)` calls `producer` with no
+            // arguments and `consumer` with the values it returns.
+            Control::CallWithValues => {
+                builtins::check_arity(name, &args, 2, Some(2))?;
+                kont.push(Pending::Values {
+                    consumer: args[1].clone(),
+                });
+                Ok(Step::Call(args[0].clone(), Vec::new()))
+            }
+            // `(with-exception-handler handler thunk)` calls `thunk` with
+            // `handler` installed. An exception calls `handler` with the
+            // condition where it was raised, with the outer handlers
+            // installed. Its value is returned from `raise-continuable`;
+            // returning from any other exception is an error.
+            Control::WithExceptionHandler => {
+                builtins::check_arity(name, &args, 2, Some(2))?;
+                kont.push(Pending::Handler {
+                    handler: args[0].clone(),
+                });
+                Ok(Step::Call(args[1].clone(), Vec::new()))
+            }
+            // `(backtrace)` lists the calls in progress, most recent first,
+            // as strings. Inside an exception handler it lists the calls that
+            // were in progress when the exception was raised.
+            Control::Backtrace => {
+                builtins::check_arity(name, &args, 0, Some(0))?;
+                let frames = kont
+                    .frames()
+                    .find_map(|pending| match pending {
+                        Pending::Handling { backtrace } => Some(backtrace.to_vec()),
+                        Pending::Raised { err, .. } => Some(err.backtrace().to_vec()),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| kont.backtrace());
+                Ok(Step::Value(LispValue::list(
+                    frames
+                        .iter()
+                        .map(|frame| LispValue::String(frame.to_string().into()))
+                        .collect(),
+                )))
+            }
+        }
+    }
+}
+
+/// Evaluates `expr`. Errors are annotated with the innermost form that
+/// failed, so they can be reported with its location, and with the calls
+/// that were in progress when they happened.
+fn eval(expr: &LispExpression, env: &Environment) -> Result<LispValue, LispError> {
+    execute(Step::Eval(expr.clone(), env.clone()))
+}
+
+/// Calls `func` with `args` and runs it to completion, for code outside
+/// the evaluator that needs to call back into Lisp procedures.
+fn apply(func: &LispValue, args: &[LispValue]) -> Result<LispValue, LispError> {
+    execute(Step::Call(func.clone(), args.to_vec()))
+}
+
+/// Carries out `step` and everything it leads to, until a value is left
+/// with nothing pending. An error unwinds the continuation to the innermost
+/// `guard` or exception handler, and ends the run if there is none.
+///
+/// Runs nest when Rust code calls back into Lisp, and the continuation of a
+/// nested run ends where the run does. A continuation from an outer run
+/// escapes to it, abandoning the runs in between; one from a nested run
+/// that has finished can't be resumed.
+fn execute(step: Step) -> Result<LispValue, LispError> {
+    let run = Run::begin();
+    let mut kont = Continuation::default();
+    // The innermost form being evaluated, for locating errors and calls.
+    let mut form = None;
+    let mut step = step;
+    loop {
+        let result = match step {
+            Step::Value(value) => match kont.pop() {
+                Some(pending) => {
+                    if let Some(list) = pending.form() {
+                        form = Some(LispExpression::List(list.clone()));
                     }
+                    pending.resume(value)
+                }
+                None => return Ok(value),
+            },
+            Step::Eval(expr, env) => {
+                let result = eval_expr(&expr, &env);
+                form = Some(expr);
+                result
+            }
+            Step::Call(func, args) => {
+                let site = form.as_ref().and_then(LispExpression::span);
+                call(func, args, site, run.id(), &mut kont)
+            }
+            Step::Push(pending, next) => {
+                kont.push(pending);
+                Ok(*next)
+            }
+        };
+        step = match result {
+            Ok(step) => step,
+            Err(LispError::Escape {
+                run: target,
+                kont: resume,
+                value,
+                ..
+            }) if target == run.id() => {
+                kont = resume;
+                Step::Value(value)
+            }
+            Err(err @ LispError::Escape { .. }) => return Err(err),
+            Err(err) => {
+                let err = match &form {
+                    Some(form) => err.in_form(form),
+                    None => err,
+                };
+                unwind(err.with_backtrace(|| kont.backtrace()), &mut kont)?
+            }
+        };
+    }
+}
+
+/// Hands `err` to the innermost `guard` or exception handler in `kont`.
+/// A `guard` drops everything pending above it. A handler is called where
+/// the exception was raised, or, when it can't return there, with the
+/// continuation of `with-exception-handler` instead. Handlers already at
+/// work are skipped. Returns the error if nothing handles it.
+fn unwind(mut err: LispError, kont: &mut Continuation) -> Result<Step, LispError> {
+    let mut search = kont.clone();
+    while let Some(pending) = search.pop() {
+        let handled = match pending {
+            Pending::Guard { var, clauses, env } => {
+                let env = env.extend(vec![(var.key().to_string(), err.condition())]);
+                *kont = search.clone();
+                kont.push(Pending::Handling {
+                    backtrace: err.backtrace().into(),
+                });
+                eval_clauses(clauses.items.clone(), 1, env, clauses, Some(Rc::new(err)))
+            }
+            Pending::Handler { handler } => {
+                let condition = err.condition();
+                if !err.is_continuable() {
+                    *kont = search.clone();
                 }
+                kont.push(Pending::Raised {
+                    err: Rc::new(err),
+                    outer: search,
+                });
+                return Ok(Step::Call(handler, vec![condition]));
             }
-            eval(&body, &mut new_env)
+            Pending::Raised { outer, .. } => {
+                search = outer;
+                continue;
+            }
+            _ => continue,
+        };
+        match handled {
+            Ok(step) => return Ok(step),
+            Err(reraised) => err = reraised,
         }
-        _ => Err("Invalid function application".to_string()),
     }
+    Err(err)
 }
 
-fn parse(tokens: &[&str]) -> Result<LispExpression, String> {
-    let mut tokens = tokens.iter();
-    parse_tokens(&mut tokens.map(|&s| s))
+fn eval_expr(expr: &LispExpression, env: &Environment) -> Result<Step, LispError> {
+    match (eval_atom(expr, env), expr) {
+        (Some(value), _) => value.map(Step::Value),
+        (None, LispExpression::List(list)) => eval_list(list, env),
+        (None, _) => Err(LispError::syntax("Cannot evaluate an improper list")),
+    }
 }
 
-fn parse_tokens(tokens: &mut dyn Iterator<Item = &str>) -> Result<LispExpression, String> {
-    let token = match tokens.next() {
-        Some(token) => token,
-        None => return Err("Unexpected end of input".to_string()),
+/// Evaluates literals and variables, which need no further steps. Returns
+/// `None` for lists.
+fn eval_atom(expr: &LispExpression, env: &Environment) -> Option<Result<LispValue, LispError>> {
+    let value = match expr {
+        LispExpression::Number(num) => LispValue::Number(num.clone()),
+        LispExpression::Boolean(b) => LispValue::Boolean(*b),
+        LispExpression::Char(c) => LispValue::Char(*c),
+        LispExpression::String(string) => LispValue::String(string.clone()),
+        LispExpression::Symbol(sym) => {
+            return Some(match env.lookup(sym) {
+                Some(LispValue::PatternVariable(_)) => Err(LispError::syntax(format!(
+                    "pattern variable {} used outside of syntax",
+                    sym
+                ))
+                .in_form(expr)),
+                Some(value) => Ok(value),
+                None => Err(LispError::unbound(sym).in_form(expr)),
+            })
+        }
+        LispExpression::List(_) | LispExpression::DottedList(_, _) => return None,
+    };
+    Some(Ok(value))
+}
+
+/// Calls `func` with `args` from a call at `site` in `run`. A lambda's body
+/// is left to be evaluated, with the call recorded in `kont`.
+fn call(
+    func: LispValue,
+    mut args: Vec<LispValue>,
+    site: Option<&Span>,
+    run: usize,
+    kont: &mut Continuation,
+) -> Result<Step, LispError> {
+    // Builtins return before anything else happens, so their calls are
+    // only recorded in the backtraces of their errors.
+    let in_call = |err: LispError, kont: &Continuation| {
+        err.with_backtrace(|| {
+            iter::once(CallFrame::new(&func, site))
+                .chain(kont.backtrace())
+                .collect()
+        })
     };
+    match &func {
+        LispValue::Lambda(closure) => {
+            kont.enter(CallFrame::new(&func, site));
+            let arity = closure.params.len();
+            if args.len() < arity || (closure.rest.is_none() && args.len() > arity) {
+                let expected = if closure.rest.is_some() {
+                    format!("at least {}", arity)
+                } else {
+                    arity.to_string()
+                };
+                return Err(LispError::arity(&func.to_string(), expected, args.len()));
+            }
+            let rest = closure
+                .rest
+                .as_ref()
+                .map(|rest| (rest.clone(), LispValue::list(args.split_off(arity))));
+            let mut bindings: Vec<_> = closure.params.iter().cloned().zip(args).collect();
+            bindings.extend(rest);
+            Ok(eval_body(
+                closure.body.clone(),
+                0,
+                &closure.env.extend(bindings),
+            ))
+        }
+        LispValue::Builtin(_, builtin) => builtin(&args)
+            .map(Step::Value)
+            .map_err(|err| in_call(err, kont)),
+        LispValue::Control(name, control) => control
+            .call(name, args, kont)
+            .map_err(|err| in_call(err, kont)),
+        LispValue::Continuation(captured) => {
+            let value = match args.len() {
+                0 => LispValue::Unspecified,
+                _ => LispValue::values(args),
+            };
+            match captured.owner() {
+                Some(owner) if owner == run => {
+                    *kont = captured.kont.clone();
+                    Ok(Step::Value(value))
+                }
+                Some(owner) => Err(LispError::escape(owner, captured.kont.clone(), value)),
+                None => Err(LispError::runtime(
+                    "continuation can't be resumed once the evaluation it was captured in has finished",
+                )),
+            }
+        }
+        _ => Err(LispError::type_error("call", "procedure", &func)),
+    }
+}
 
-    match token {
-        "(" => parse_list(tokens),
-        ")" => Err("Unexpected ')'".to_string()),
-        "true" => Ok(LispExpression::Boolean(true)),
-        "false" => Ok(LispExpression::Boolean(false)),
-        _ => {
-            if let Ok(num) = token.parse::<f64>() {
-                Ok(LispExpression::Number(num))
+fn eval_list(list: &List, env: &Environment) -> Result<Step, LispError> {
+    if list.is_empty() {
+        return Err(LispError::syntax("Empty list"));
+    }
+    match &list[0] {
+        LispExpression::Symbol(s) if s == "define" => eval_define(list, env),
+        LispExpression::Symbol(s) if s == "define-syntax" => {
+            eval_define_syntax(list, env).map(Step::Value)
+        }
+        LispExpression::Symbol(s) if s == "let-syntax" => eval_let_syntax(list, env, false),
+        LispExpression::Symbol(s) if s == "letrec-syntax" => eval_let_syntax(list, env, true),
+        LispExpression::Symbol(s) if s == "syntax" => {
+            if list.len() != 2 {
+                return Err(LispError::syntax("Invalid syntax expression"));
+            }
+            syntax::syntax_template(&list[1], env).map(Step::Value)
+        }
+        LispExpression::Symbol(s) if s == "syntax-case" => eval_syntax_case(list, env),
+        LispExpression::Symbol(s) if s == "with-syntax" => eval_with_syntax(list, env),
+        LispExpression::Symbol(s) if s == "define-macro" || s == "defmacro" => {
+            eval_define_macro(list, env).map(Step::Value)
+        }
+        LispExpression::Symbol(s) if s == "set!" => {
+            if list.len() != 3 {
+                return Err(LispError::syntax("Invalid set! expression"));
+            }
+            if let LispExpression::Symbol(name) = &list[1] {
+                let set = Pending::Set {
+                    name: name.clone(),
+                    form: list.clone(),
+                    env: env.clone(),
+                };
+                Ok(then(set, &list[2], env))
             } else {
-                Ok(LispExpression::Symbol(token.to_string()))
+                Err(LispError::syntax("Invalid variable name in set!"))
+            }
+        }
+        LispExpression::Symbol(s) if s == "quote" => {
+            if list.len() != 2 {
+                return Err(LispError::syntax("Invalid quote expression"));
+            }
+            Ok(Step::Value(LispValue::from_datum(&list[1])))
+        }
+        LispExpression::Symbol(s) if s == "quasiquote" => {
+            if list.len() != 2 {
+                return Err(LispError::syntax("Invalid quasiquote expression"));
+            }
+            let mut exprs = Vec::new();
+            unquoted(&list[1], 1, &mut exprs);
+            eval_quasiquote(list.clone(), exprs.into(), Vec::new(), env)
+        }
+        LispExpression::Symbol(s) if s == "unquote" || s == "unquote-splicing" => {
+            Err(LispError::syntax(format!("{} outside of quasiquote", s)))
+        }
+        LispExpression::Symbol(s) if s == "lambda" => {
+            if list.len() < 3 {
+                return Err(LispError::syntax("Invalid lambda expression"));
+            }
+            make_lambda(&list[1], &list[2..], env, None).map(Step::Value)
+        }
+        LispExpression::Symbol(s) if s == "let" => eval_let(list, env),
+        LispExpression::Symbol(s) if s == "let*" => eval_let_star(list, env),
+        LispExpression::Symbol(s) if s == "letrec" => eval_letrec(list, env, false),
+        LispExpression::Symbol(s) if s == "letrec*" => eval_letrec(list, env, true),
+        LispExpression::Symbol(s) if s == "if" => eval_if(list, env),
+        LispExpression::Symbol(s) if s == "cond" => eval_cond(list, env),
+        LispExpression::Symbol(s) if s == "case" => eval_case(list, env),
+        LispExpression::Symbol(s) if s == "when" => eval_when(list, env, true),
+        LispExpression::Symbol(s) if s == "unless" => eval_when(list, env, false),
+        LispExpression::Symbol(s) if s == "begin" => Ok(eval_body(list.items.clone(), 1, env)),
+        LispExpression::Symbol(s) if s == "and" => Ok(eval_and(list.clone(), 1, env)),
+        LispExpression::Symbol(s) if s == "or" => Ok(eval_or(list.clone(), 1, env)),
+        LispExpression::Symbol(s) if s == "guard" => eval_guard(list, env),
+        LispExpression::Symbol(s) if s == "include" => eval_include(list, env),
+        operator => {
+            let pending = Pending::Operator {
+                form: list.clone(),
+                env: env.clone(),
+            };
+            match eval_atom(operator, env) {
+                Some(func) => pending.resume(func?),
+                None => Ok(then(pending, operator, env)),
             }
         }
     }
 }
 
-fn parse_list(tokens: &mut dyn Iterator<Item = &str>) -> Result<LispExpression, String> {
-    let mut list = Vec::new();
+/// Continues a combination whose operator evaluated to `func`: expands it
+/// if `func` is a macro, and otherwise evaluates the operands to call it.
+fn eval_operands(func: LispValue, form: List, env: &Environment) -> Result<Step, LispError> {
+    match &func {
+        LispValue::Macro(transformer) => {
+            return Ok(Step::Eval(expand_macro(transformer, &form)?, env.clone()))
+        }
+        LispValue::Syntax(rules) => return Ok(Step::Eval(rules.expand(&form, env)?, env.clone())),
+        LispValue::Transformer(transformer) => {
+            return Ok(Step::Eval(
+                syntax::expand_procedure(transformer, &form, env)?,
+                env.clone(),
+            ))
+        }
+        _ => {}
+    }
+    let values = Vec::with_capacity(form.len() - 1);
+    eval_arguments(func, form.items.clone(), 1, values, form, env)
+}
 
-    loop {
-        let token = match tokens.next() {
-            Some(token) => token,
-            None => return Err("Unexpected end of input inside list".to_string()),
-        };
+/// Evaluates the arguments from `exprs[index]` on, then calls `func` with
+/// them added to `values`. Literals and variables are evaluated on the spot.
+fn eval_arguments(
+    func: LispValue,
+    exprs: Rc<[LispExpression]>,
+    mut index: usize,
+    mut values: Vec<LispValue>,
+    form: List,
+    env: &Environment,
+) -> Result<Step, LispError> {
+    while let Some(expr) = exprs.get(index) {
+        index += 1;
+        match eval_atom(expr, env) {
+            Some(value) => values.push(value?),
+            None => {
+                let expr = expr.clone();
+                let pending = Pending::Arguments {
+                    func,
+                    exprs,
+                    index,
+                    values,
+                    form,
+                    env: env.clone(),
+                };
+                return Ok(then(pending, &expr, env));
+            }
+        }
+    }
+    Ok(Step::Call(func, values))
+}
+
+/// Evaluates `(define name value)` or the procedure shorthand
+/// `(define (name params ...) body ...)`.
+fn eval_define(list: &[LispExpression], env: &Environment) -> Result<Step, LispError> {
+    if list.len() < 3 {
+        return Err(LispError::syntax("Invalid define expression"));
+    }
+    let (name, value) = match &list[1] {
+        LispExpression::Symbol(name) => {
+            if list.len() != 3 {
+                return Err(LispError::syntax("Invalid define expression"));
+            }
+            // `(define f (lambda ...))` names the procedure like the
+            // `(define (f ...) ...)` shorthand does.
+            let value = match &list[2] {
+                LispExpression::List(lambda)
+                    if lambda.len() >= 3 && is_symbol(&lambda[0], "lambda") =>
+                {
+                    make_lambda(&lambda[1], &lambda[2..], env, Some(name))?
+                }
+                value => {
+                    let define = Pending::Define {
+                        name: name.clone(),
+                        env: env.clone(),
+                    };
+                    return Ok(then(define, value, env));
+                }
+            };
+            (name, value)
+        }
+        LispExpression::List(signature) if !signature.is_empty() => match &signature[0] {
+            LispExpression::Symbol(name) => {
+                let params = LispExpression::List(signature[1..].into());
+                (name, make_lambda(&params, &list[2..], env, Some(name))?)
+            }
+            _ => return Err(LispError::syntax("Invalid variable name in define")),
+        },
+        LispExpression::DottedList(signature, rest) => match &signature[0] {
+            LispExpression::Symbol(name) => {
+                let params = if signature.len() == 1 {
+                    (**rest).clone()
+                } else {
+                    LispExpression::DottedList(signature[1..].into(), rest.clone())
+                };
+                (name, make_lambda(&params, &list[2..], env, Some(name))?)
+            }
+            _ => return Err(LispError::syntax("Invalid variable name in define")),
+        },
+        _ => return Err(LispError::syntax("Invalid variable name in define")),
+    };
+    env.define(name.key().to_string(), value.clone());
+    Ok(Step::Value(value))
+}
 
-        match token {
-            "(" => {
-                let sub_expr = parse_list(tokens)?;
-                list.push(sub_expr);
+/// Evaluates `(define-macro (name params ...) body ...)`,
+/// `(define-macro name transformer)` or `(defmacro name (params ...) body ...)`.
+/// The transformer receives the operands of each use unevaluated and returns
+/// the code to evaluate in its place.
+fn eval_define_macro(list: &[LispExpression], env: &Environment) -> Result<LispValue, LispError> {
+    let (name, transformer) = match (&list[0], list.get(1)) {
+        (LispExpression::Symbol(keyword), Some(LispExpression::Symbol(name)))
+            if keyword == "defmacro" =>
+        {
+            if list.len() < 4 {
+                return Err(LispError::syntax("Invalid defmacro expression"));
             }
-            ")" => return Ok(LispExpression::List(list)),
-            _ => {
-                let expr = parse_tokens(&mut std::iter::once(token))?;
-                list.push(expr);
+            (name, make_lambda(&list[2], &list[3..], env, Some(name))?)
+        }
+        (_, Some(LispExpression::Symbol(name))) if list.len() == 3 => (name, eval(&list[2], env)?),
+        (_, Some(LispExpression::List(signature))) if list.len() >= 3 => match signature.first() {
+            Some(LispExpression::Symbol(name)) => {
+                let params = LispExpression::List(signature[1..].into());
+                (name, make_lambda(&params, &list[2..], env, Some(name))?)
+            }
+            _ => return Err(LispError::syntax("Invalid macro name in define-macro")),
+        },
+        (_, Some(LispExpression::DottedList(signature, rest))) if list.len() >= 3 => {
+            match &signature[0] {
+                LispExpression::Symbol(name) => {
+                    let params = if signature.len() == 1 {
+                        (**rest).clone()
+                    } else {
+                        LispExpression::DottedList(signature[1..].into(), rest.clone())
+                    };
+                    (name, make_lambda(&params, &list[2..], env, Some(name))?)
+                }
+                _ => return Err(LispError::syntax("Invalid macro name in define-macro")),
+            }
+        }
+        _ => return Err(LispError::syntax("Invalid define-macro expression")),
+    };
+    let transformer = match transformer {
+        LispValue::Lambda(closure) => LispValue::Macro(closure),
+        other => return Err(LispError::type_error("define-macro", "procedure", &other)),
+    };
+    env.define(name.key().to_string(), transformer);
+    Ok(LispValue::Symbol(name.to_string()))
+}
+
+/// Expands one use of a macro. The expansion is given the location of the
+/// form it replaces, so errors in it point at the macro call.
+fn expand_macro(transformer: &Rc<Closure>, form: &List) -> Result<LispExpression, LispError> {
+    let operands: Vec<_> = form[1..].iter().map(LispValue::from_datum).collect();
+    let expansion = apply(&LispValue::Lambda(transformer.clone()), &operands)?;
+    LispExpression::from_value(&expansion, form.span.as_ref())
+}
+
+/// Expands `form` once if it is a use of a macro bound in `env`.
+fn macroexpand_1(
+    form: &LispExpression,
+    env: &Environment,
+) -> Result<Option<LispExpression>, LispError> {
+    if let LispExpression::List(list) = form {
+        if let Some(LispExpression::Symbol(name)) = list.first() {
+            match env.lookup(name) {
+                Some(LispValue::Macro(transformer)) => {
+                    return expand_macro(&transformer, list).map(Some)
+                }
+                Some(LispValue::Syntax(rules)) => return rules.expand(list, env).map(Some),
+                Some(LispValue::Transformer(transformer)) => {
+                    return syntax::expand_procedure(&transformer, list, env).map(Some)
+                }
+                _ => {}
             }
         }
     }
+    Ok(None)
 }
-fn main() {
-    let mut env = Environment::new();
 
-    loop {
-        print!("> ");
-        io::stdout().flush().unwrap();
+/// Builds the macro a `define-syntax`, `let-syntax` or `letrec-syntax`
+/// binding describes: a `syntax-rules` form, a procedure taking a syntax
+/// object, or another macro.
+fn make_syntax(
+    spec: &LispExpression,
+    name: &str,
+    env: &Environment,
+) -> Result<LispValue, LispError> {
+    let transformer = match spec {
+        LispExpression::List(spec)
+            if spec
+                .first()
+                .is_some_and(|keyword| is_symbol(keyword, "syntax-rules")) =>
+        {
+            return Ok(LispValue::Syntax(Rc::new(SyntaxRules::new(
+                spec,
+                Some(name),
+                env,
+            )?)));
+        }
+        LispExpression::List(lambda) if lambda.len() >= 3 && is_symbol(&lambda[0], "lambda") => {
+            make_lambda(&lambda[1], &lambda[2..], env, Some(name))?
+        }
+        spec => eval(spec, env)?,
+    };
+    match transformer {
+        LispValue::Lambda(closure) => Ok(LispValue::Transformer(closure)),
+        value @ (LispValue::Syntax(_) | LispValue::Macro(_) | LispValue::Transformer(_)) => {
+            Ok(value)
+        }
+        other => Err(LispError::type_error(name, "syntax transformer", &other)),
+    }
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+/// Binds the pattern variables of a successful match in a new scope.
+fn bind_pattern_variables(env: &Environment, bindings: Bindings) -> Environment {
+    env.extend(
+        bindings
+            .into_iter()
+            .map(|(key, binding)| (key, LispValue::PatternVariable(Rc::new(binding))))
+            .collect(),
+    )
+}
 
-        let trimmed_input = input.trim();
+/// `(syntax-case expr (literal ...) clause ...)` matches the syntax `expr`
+/// evaluates to against each clause's pattern and evaluates the output of
+/// the first that matches. A clause is `(pattern output)` or
+/// `(pattern fender output)`, where the fender must also return true.
+fn eval_syntax_case(list: &List, env: &Environment) -> Result<Step, LispError> {
+    if list.len() < 3 {
+        return Err(LispError::syntax("Invalid syntax-case expression"));
+    }
+    let pending = Pending::SyntaxCase {
+        form: list.clone(),
+        env: env.clone(),
+    };
+    Ok(then(pending, &list[1], env))
+}
 
-        if trimmed_input == "exit" {
-            break;
+/// Tries the clauses of the `syntax-case` expression `form` from
+/// `form[index]` on against `input`.
+fn eval_syntax_clauses(
+    form: List,
+    input: LispValue,
+    index: usize,
+    env: &Environment,
+) -> Result<Step, LispError> {
+    let (expr, expr_env) = syntax::syntax_parts(&input, env)?;
+    let literals = syntax::parse_literals(&form[2])?;
+    for (index, clause) in form.iter().enumerate().skip(index) {
+        let (pattern, fender, output) = match clause {
+            LispExpression::List(clause) if clause.len() == 2 => (&clause[0], None, &clause[1]),
+            LispExpression::List(clause) if clause.len() == 3 => {
+                (&clause[0], Some(&clause[1]), &clause[2])
+            }
+            _ => return Err(LispError::syntax("Invalid syntax-case clause")),
+        };
+        if let Some(bindings) = syntax::match_syntax(pattern, &expr, &literals, env, &expr_env) {
+            let clause_env = bind_pattern_variables(env, bindings);
+            let fender = match fender {
+                Some(fender) => fender.clone(),
+                None => return Ok(Step::Eval(output.clone(), clause_env)),
+            };
+            let pending = Pending::Fender {
+                form: form.clone(),
+                input,
+                index,
+                env: env.clone(),
+                clause_env: clause_env.clone(),
+            };
+            return Ok(then(pending, &fender, &clause_env));
         }
+    }
+    Err(LispError::syntax(format!(
+        "syntax-case: no clause matches {}",
+        expr
+    )))
+}
 
-        let tokens: Vec<&str> = trimmed_input.split_whitespace().collect();
-        match parse(&tokens) {
-            Ok(expr) => match eval(&expr, &mut env) {
-                Ok(value) => println!("{:?}", value),
-                Err(err) => eprintln!("Error: {}", err),
+/// `(with-syntax ((pattern expr) ...) body ...)` matches each pattern
+/// against the syntax its expression evaluates to, and evaluates the body
+/// with all their pattern variables bound.
+fn eval_with_syntax(list: &List, env: &Environment) -> Result<Step, LispError> {
+    match list.get(1) {
+        Some(LispExpression::List(_)) if list.len() >= 3 => {
+            eval_with_syntax_specs(list.clone(), 0, Bindings::new(), env)
+        }
+        _ => Err(LispError::syntax("Invalid with-syntax expression")),
+    }
+}
+
+/// The `(pattern expr)` spec at `index` of the `with-syntax` expression
+/// `form`, if there is one.
+fn with_syntax_spec(
+    form: &List,
+    index: usize,
+) -> Result<Option<(&LispExpression, &LispExpression)>, LispError> {
+    let specs = match &form[1] {
+        LispExpression::List(specs) => specs,
+        _ => return Err(LispError::syntax("Invalid with-syntax expression")),
+    };
+    match specs.get(index) {
+        Some(LispExpression::List(spec)) if spec.len() == 2 => Ok(Some((&spec[0], &spec[1]))),
+        Some(_) => Err(LispError::syntax("Invalid binding in with-syntax")),
+        None => Ok(None),
+    }
+}
+
+/// Evaluates the specs of the `with-syntax` expression `form` from
+/// `index` on, then its body with `bindings` and theirs bound.
+fn eval_with_syntax_specs(
+    form: List,
+    index: usize,
+    bindings: Bindings,
+    env: &Environment,
+) -> Result<Step, LispError> {
+    let expr = match with_syntax_spec(&form, index)? {
+        Some((_, expr)) => expr.clone(),
+        None => {
+            return Ok(eval_body(
+                form.items.clone(),
+                2,
+                &bind_pattern_variables(env, bindings),
+            ))
+        }
+    };
+    let pending = Pending::WithSyntax {
+        form,
+        index,
+        bindings,
+        env: env.clone(),
+    };
+    Ok(then(pending, &expr, env))
+}
+
+/// Matches the pattern of the `with-syntax` spec at `index` against
+/// `value`, then carries on with the next spec.
+fn bind_with_syntax(
+    form: List,
+    index: usize,
+    mut bindings: Bindings,
+    value: LispValue,
+    env: &Environment,
+) -> Result<Step, LispError> {
+    if let Some((pattern, _)) = with_syntax_spec(&form, index)? {
+        let (expr, expr_env) = syntax::syntax_parts(&value, env)?;
+        match syntax::match_syntax(pattern, &expr, &[], env, &expr_env) {
+            Some(matched) => bindings.extend(matched),
+            None => {
+                return Err(LispError::syntax(format!(
+                    "with-syntax: {} does not match {}",
+                    expr, pattern
+                )))
+            }
+        }
+    }
+    eval_with_syntax_specs(form, index + 1, bindings, env)
+}
+
+/// Evaluates `(define-syntax name transformer)`.
+fn eval_define_syntax(list: &[LispExpression], env: &Environment) -> Result<LispValue, LispError> {
+    match list {
+        [_, LispExpression::Symbol(name), spec] => {
+            env.define(name.key().to_string(), make_syntax(spec, name, env)?);
+            Ok(LispValue::Symbol(name.to_string()))
+        }
+        _ => Err(LispError::syntax("Invalid define-syntax expression")),
+    }
+}
+
+/// Evaluates `(let-syntax ((name transformer) ...) body ...)`. With
+/// `recursive`, as for `letrec-syntax`, the transformers are defined in the
+/// scope they create, so they can use each other.
+fn eval_let_syntax(list: &List, env: &Environment, recursive: bool) -> Result<Step, LispError> {
+    let form = if recursive {
+        "letrec-syntax"
+    } else {
+        "let-syntax"
+    };
+    if list.len() < 3 {
+        return Err(LispError::syntax(format!("Invalid {} expression", form)));
+    }
+    let body_env = env.extend(Vec::new());
+    let spec_env = if recursive { &body_env } else { env };
+    for (name, spec) in parse_bindings(&list[1], form)? {
+        body_env.define(name.clone(), make_syntax(spec, &name, spec_env)?);
+    }
+    Ok(eval_body(list.items.clone(), 2, &body_env))
+}
+
+/// Returns `(keyword x)` as `Some(x)`; used to spot quasiquote markers.
+fn quote_form<'a>(expr: &'a LispExpression, keyword: &str) -> Option<&'a LispExpression> {
+    match expr {
+        LispExpression::List(list) if list.len() == 2 && is_symbol(&list[0], keyword) => {
+            Some(&list[1])
+        }
+        _ => None,
+    }
+}
+
+/// Collects the expressions a quasiquoted template unquotes, in the order
+/// `quasiquote` uses their values. `depth` counts the enclosing quasiquotes,
+/// so only unquotes that bring it to zero are evaluated.
+fn unquoted(template: &LispExpression, depth: usize, exprs: &mut Vec<LispExpression>) {
+    if let Some(inner) = quote_form(template, "unquote") {
+        return if depth == 1 {
+            exprs.push(inner.clone())
+        } else {
+            unquoted(inner, depth - 1, exprs)
+        };
+    }
+    if let Some(inner) = quote_form(template, "quasiquote") {
+        return unquoted(inner, depth + 1, exprs);
+    }
+    let (items, tail) = match template {
+        LispExpression::List(list) => (&list.items, None),
+        LispExpression::DottedList(items, tail) => (items, Some(tail)),
+        _ => return,
+    };
+    for item in items.iter() {
+        match quote_form(item, "unquote-splicing") {
+            Some(inner) if depth == 1 => exprs.push(inner.clone()),
+            Some(inner) => unquoted(inner, depth - 1, exprs),
+            None => unquoted(item, depth, exprs),
+        }
+    }
+    if let Some(tail) = tail {
+        unquoted(tail, depth, exprs);
+    }
+}
+
+/// Builds the data of a quasiquoted template, taking the values of the
+/// expressions `unquoted` collected from `values`.
+fn quasiquote(
+    template: &LispExpression,
+    depth: usize,
+    values: &mut vec::IntoIter<LispValue>,
+) -> Result<LispValue, LispError> {
+    let nested = |keyword: &str,
+                  inner: &LispExpression,
+                  depth: usize,
+                  values: &mut vec::IntoIter<LispValue>| {
+        Ok(LispValue::list(vec![
+            LispValue::Symbol(keyword.to_string()),
+            quasiquote(inner, depth, values)?,
+        ]))
+    };
+    if let Some(inner) = quote_form(template, "unquote") {
+        return if depth == 1 {
+            Ok(values.next().expect("a value for each unquoted expression"))
+        } else {
+            nested("unquote", inner, depth - 1, values)
+        };
+    }
+    if let Some(inner) = quote_form(template, "quasiquote") {
+        return nested("quasiquote", inner, depth + 1, values);
+    }
+    let (items, tail) = match template {
+        LispExpression::List(list) => (&list.items, None),
+        LispExpression::DottedList(items, tail) => (items, Some(tail)),
+        _ => return Ok(LispValue::from_datum(template)),
+    };
+    let mut result = Vec::new();
+    for item in items.iter() {
+        match quote_form(item, "unquote-splicing") {
+            Some(_) if depth == 1 => {
+                let spliced = values.next().expect("a value for each unquoted expression");
+                result.extend(builtins::list_items("unquote-splicing", &spliced)?);
+            }
+            Some(inner) => result.push(nested("unquote-splicing", inner, depth - 1, values)?),
+            None => result.push(quasiquote(item, depth, values)?),
+        }
+    }
+    let tail = match tail {
+        Some(tail) => quasiquote(tail, depth, values)?,
+        None => LispValue::Nil,
+    };
+    Ok(LispValue::list_with_tail(result, tail))
+}
+
+/// Evaluates the unquoted expressions of the quasiquote `form` after the
+/// `values` already evaluated, then builds its data.
+fn eval_quasiquote(
+    form: List,
+    exprs: Rc<[LispExpression]>,
+    mut values: Vec<LispValue>,
+    env: &Environment,
+) -> Result<Step, LispError> {
+    while let Some(expr) = exprs.get(values.len()) {
+        match eval_atom(expr, env) {
+            Some(value) => values.push(value?),
+            None => {
+                let expr = expr.clone();
+                let pending = Pending::Quasiquote {
+                    form,
+                    exprs,
+                    values,
+                    env: env.clone(),
+                };
+                return Ok(then(pending, &expr, env));
+            }
+        }
+    }
+    quasiquote(&form[1], 1, &mut values.into_iter()).map(Step::Value)
+}
+
+/// Builds a closure from a parameter list, which may be `(a b)`, the
+/// variadic `(a b . rest)`, or a single symbol that receives every argument.
+fn make_lambda(
+    params: &LispExpression,
+    body: &[LispExpression],
+    env: &Environment,
+    name: Option<&str>,
+) -> Result<LispValue, LispError> {
+    let (fixed, rest): (&[LispExpression], Option<&LispExpression>) = match params {
+        LispExpression::List(params) => (params, None),
+        LispExpression::DottedList(params, rest) => (params, Some(rest)),
+        LispExpression::Symbol(_) => (&[], Some(params)),
+        _ => return Err(LispError::syntax("Invalid parameter list in lambda")),
+    };
+    let param_name = |param: &LispExpression| match param {
+        LispExpression::Symbol(name) => Ok(name.key().to_string()),
+        _ => Err(LispError::syntax("Invalid parameter name in lambda")),
+    };
+    Ok(LispValue::Lambda(Rc::new(Closure {
+        name: name.map(str::to_string),
+        params: fixed.iter().map(param_name).collect::<Result<_, _>>()?,
+        rest: rest.map(param_name).transpose()?,
+        body: body.into(),
+        env: env.clone(), // Capture the current environment
+    })))
+}
+
+/// Splits the `((name init) ...)` list of a `let`-style form into its names
+/// and initializer expressions.
+fn parse_bindings<'a>(
+    bindings: &'a LispExpression,
+    form: &str,
+) -> Result<Vec<(String, &'a LispExpression)>, LispError> {
+    let bindings = match bindings {
+        LispExpression::List(bindings) => bindings,
+        _ => return Err(LispError::syntax(format!("Invalid {} expression", form))),
+    };
+    bindings
+        .iter()
+        .map(|binding| match binding {
+            LispExpression::List(pair) if pair.len() == 2 => match &pair[0] {
+                LispExpression::Symbol(name) => Ok((name.key().to_string(), &pair[1])),
+                _ => Err(LispError::syntax(format!(
+                    "Invalid variable name in {}",
+                    form
+                ))),
             },
-            Err(err) => eprintln!("Error parsing input: {}", err),
+            _ => Err(LispError::syntax(format!("Invalid binding in {}", form))),
+        })
+        .collect()
+}
+
+/// Like `parse_bindings`, but keeps the bindings for the evaluator to work
+/// through one init at a time.
+fn let_bindings(bindings: &LispExpression, form: &str) -> Result<LetBindings, LispError> {
+    Ok(parse_bindings(bindings, form)?
+        .into_iter()
+        .map(|(name, init)| (name, init.clone()))
+        .collect())
+}
+
+fn eval_let(list: &List, env: &Environment) -> Result<Step, LispError> {
+    if let Some(LispExpression::Symbol(name)) = list.get(1) {
+        return eval_named_let(name, list, env);
+    }
+    if list.len() < 3 {
+        return Err(LispError::syntax("Invalid let expression"));
+    }
+    let bindings = let_bindings(&list[1], "let")?;
+    Ok(eval_let_inits(
+        bindings,
+        Vec::new(),
+        list.clone(),
+        env.clone(),
+    ))
+}
+
+/// Evaluates the inits of a `let` after the `values` already evaluated,
+/// then its body with the variables bound.
+fn eval_let_inits(
+    bindings: LetBindings,
+    values: Vec<LispValue>,
+    form: List,
+    env: Environment,
+) -> Step {
+    match bindings.get(values.len()) {
+        Some((_, init)) => {
+            let init = init.clone();
+            let pending = Pending::Let {
+                bindings,
+                values,
+                form,
+                env: env.clone(),
+            };
+            then(pending, &init, &env)
+        }
+        None => {
+            let names = bindings.iter().map(|(name, _)| name.clone());
+            eval_body(
+                form.items.clone(),
+                2,
+                &env.extend(names.zip(values).collect()),
+            )
+        }
+    }
+}
+
+/// `(let name ((var init) ...) body ...)` binds `name` to a procedure over
+/// the variables, visible only inside the body, and calls it with the inits.
+fn eval_named_let(name: &Identifier, list: &List, env: &Environment) -> Result<Step, LispError> {
+    if list.len() < 4 {
+        return Err(LispError::syntax("Invalid let expression"));
+    }
+    let bindings = parse_bindings(&list[2], "let")?;
+    let inits: Rc<[LispExpression]> = bindings.iter().map(|(_, init)| (*init).clone()).collect();
+    let loop_env = env.extend(Vec::new());
+    let procedure = LispValue::Lambda(Rc::new(Closure {
+        name: Some(name.to_string()),
+        params: bindings.into_iter().map(|(param, _)| param).collect(),
+        rest: None,
+        body: list[3..].into(),
+        env: loop_env.clone(),
+    }));
+    loop_env.define(name.key().to_string(), procedure.clone());
+    eval_arguments(procedure, inits, 0, Vec::new(), list.clone(), env)
+}
+
+fn eval_let_star(list: &List, env: &Environment) -> Result<Step, LispError> {
+    if list.len() < 3 {
+        return Err(LispError::syntax("Invalid let* expression"));
+    }
+    let bindings = let_bindings(&list[1], "let*")?;
+    Ok(eval_let_star_inits(bindings, 0, list.clone(), env.clone()))
+}
+
+/// Evaluates the inits of a `let*` from `bindings[index]` on in `scope`,
+/// each in the scope of the variables before it.
+fn eval_let_star_inits(
+    bindings: LetBindings,
+    index: usize,
+    form: List,
+    scope: Environment,
+) -> Step {
+    match bindings.get(index) {
+        Some((_, init)) => {
+            let init = init.clone();
+            let pending = Pending::LetStar {
+                bindings,
+                index,
+                form,
+                env: scope.clone(),
+            };
+            then(pending, &init, &scope)
+        }
+        None => eval_body(form.items.clone(), 2, &scope),
+    }
+}
+
+/// Evaluates `letrec`, or `letrec*` when `sequential` is set. The inits are
+/// evaluated in the new scope so they can refer to each other.
+fn eval_letrec(list: &List, env: &Environment, sequential: bool) -> Result<Step, LispError> {
+    let form = if sequential { "letrec*" } else { "letrec" };
+    if list.len() < 3 {
+        return Err(LispError::syntax(format!("Invalid {} expression", form)));
+    }
+    let bindings = let_bindings(&list[1], form)?;
+    let scope = env.extend(
+        bindings
+            .iter()
+            .map(|(name, _)| (name.clone(), LispValue::Unspecified))
+            .collect(),
+    );
+    Ok(eval_letrec_inits(
+        bindings,
+        0,
+        Vec::new(),
+        list.clone(),
+        scope,
+        sequential,
+    ))
+}
+
+/// Evaluates the inits of a `letrec` from `bindings[index]` on. `letrec*`
+/// defines each variable as soon as its init is evaluated; `letrec` keeps
+/// the `values` until all of them are.
+fn eval_letrec_inits(
+    bindings: LetBindings,
+    index: usize,
+    values: Vec<LispValue>,
+    form: List,
+    scope: Environment,
+    sequential: bool,
+) -> Step {
+    match bindings.get(index) {
+        Some((_, init)) => {
+            let init = init.clone();
+            let pending = Pending::Letrec {
+                bindings,
+                index,
+                values,
+                form,
+                env: scope.clone(),
+                sequential,
+            };
+            then(pending, &init, &scope)
+        }
+        None => {
+            for ((name, _), value) in bindings.iter().zip(values) {
+                scope.define(name.clone(), value);
+            }
+            eval_body(form.items.clone(), 2, &scope)
+        }
+    }
+}
+
+/// Only `false` counts as false; every other value is true.
+fn is_truthy(value: &LispValue) -> bool {
+    !matches!(value, LispValue::Boolean(false))
+}
+
+/// Evaluates `body` from `body[start]` on, leaving the last expression in
+/// tail position.
+fn eval_body(body: Rc<[LispExpression]>, start: usize, env: &Environment) -> Step {
+    match body.len().saturating_sub(start) {
+        0 => Step::Value(LispValue::Unspecified),
+        1 => Step::Eval(body[start].clone(), env.clone()),
+        _ => {
+            let expr = body[start].clone();
+            let pending = Pending::Sequence {
+                body,
+                index: start + 1,
+                env: env.clone(),
+            };
+            then(pending, &expr, env)
+        }
+    }
+}
+
+fn eval_if(list: &List, env: &Environment) -> Result<Step, LispError> {
+    if list.len() != 3 && list.len() != 4 {
+        return Err(LispError::syntax("Invalid if expression"));
+    }
+    let pending = Pending::If {
+        form: list.clone(),
+        env: env.clone(),
+    };
+    Ok(then(pending, &list[1], env))
+}
+
+fn is_symbol(expr: &LispExpression, name: &str) -> bool {
+    matches!(expr, LispExpression::Symbol(s) if s == name)
+}
+
+/// Evaluates the body of a `cond` or `case` clause whose test produced `value`,
+/// handling the `(test => receiver)` form.
+fn eval_clause_body(
+    clause: &List,
+    value: LispValue,
+    env: &Environment,
+    form: List,
+) -> Result<Step, LispError> {
+    match &clause[1..] {
+        [] => Ok(Step::Value(value)),
+        [arrow, receiver] if is_symbol(arrow, "=>") => {
+            Ok(then(Pending::Receiver { value, form }, receiver, env))
+        }
+        [arrow, ..] if is_symbol(arrow, "=>") => Err(LispError::syntax("Invalid => clause")),
+        _ => Ok(eval_body(clause.items.clone(), 1, env)),
+    }
+}
+
+fn eval_cond(list: &List, env: &Environment) -> Result<Step, LispError> {
+    check_clauses(&list[1..], "cond")?;
+    eval_clauses(list.items.clone(), 1, env.clone(), list.clone(), None)
+}
+
+/// Checks the shape of `cond`-style clauses before any of them is tried.
+fn check_clauses(clauses: &[LispExpression], form: &str) -> Result<(), LispError> {
+    for (i, clause) in clauses.iter().enumerate() {
+        match clause {
+            LispExpression::List(clause) if !clause.is_empty() => {
+                if is_symbol(&clause[0], "else") && i != clauses.len() - 1 {
+                    return Err(LispError::syntax(format!(
+                        "else must be the last {} clause",
+                        form
+                    )));
+                }
+            }
+            _ => return Err(LispError::syntax(format!("Invalid {} clause", form))),
+        }
+    }
+    Ok(())
+}
+
+/// Tries `cond`-style clauses in order from `clauses[index]` on. When none
+/// applies, the result is unspecified, or `reraise` is raised again.
+fn eval_clauses(
+    clauses: Rc<[LispExpression]>,
+    index: usize,
+    env: Environment,
+    form: List,
+    reraise: Option<Rc<LispError>>,
+) -> Result<Step, LispError> {
+    let test = match clauses.get(index) {
+        Some(LispExpression::List(clause)) if is_symbol(&clause[0], "else") => {
+            return Ok(eval_body(clause.items.clone(), 1, &env))
+        }
+        Some(LispExpression::List(clause)) => clause[0].clone(),
+        Some(_) => return Err(LispError::syntax("Invalid cond clause")),
+        None => {
+            return match reraise {
+                Some(err) => Err((*err).clone()),
+                None => Ok(Step::Value(LispValue::Unspecified)),
+            }
+        }
+    };
+    let pending = Pending::Cond {
+        clauses,
+        index,
+        env: env.clone(),
+        form,
+        reraise,
+    };
+    Ok(then(pending, &test, &env))
+}
+
+/// `(guard (var clause ...) body ...)` evaluates the body; if it raises,
+/// `var` is bound to the condition and the `cond`-style clauses are tried,
+/// with the exception raised again when none applies.
+fn eval_guard(list: &List, env: &Environment) -> Result<Step, LispError> {
+    let (var, spec) = match list.get(1) {
+        Some(LispExpression::List(spec)) if list.len() >= 3 => match spec.first() {
+            Some(LispExpression::Symbol(var)) => (var, spec),
+            _ => return Err(LispError::syntax("Invalid guard variable")),
+        },
+        _ => return Err(LispError::syntax("Invalid guard expression")),
+    };
+    check_clauses(&spec[1..], "guard")?;
+    let guard = Pending::Guard {
+        var: var.clone(),
+        clauses: spec.clone(),
+        env: env.clone(),
+    };
+    Ok(Step::Push(
+        guard,
+        Box::new(eval_body(list.items.clone(), 2, env)),
+    ))
+}
+
+/// Compares a literal `case` datum with a value the way `eqv?` would.
+fn datum_matches(datum: &LispExpression, value: &LispValue) -> bool {
+    LispValue::from_datum(datum).eqv(value)
+}
+
+fn eval_case(list: &List, env: &Environment) -> Result<Step, LispError> {
+    if list.len() < 2 {
+        return Err(LispError::syntax("Invalid case expression"));
+    }
+    let pending = Pending::Case {
+        form: list.clone(),
+        env: env.clone(),
+    };
+    Ok(then(pending, &list[1], env))
+}
+
+/// Picks the clause of the `case` expression `form` that matches `key`.
+fn eval_case_clauses(form: List, key: LispValue, env: &Environment) -> Result<Step, LispError> {
+    for (i, clause) in form[2..].iter().enumerate() {
+        let clause = match clause {
+            LispExpression::List(clause) if !clause.is_empty() => clause,
+            _ => return Err(LispError::syntax("Invalid case clause")),
+        };
+        let matched = match &clause[0] {
+            LispExpression::List(data) => data.iter().any(|datum| datum_matches(datum, &key)),
+            else_ if is_symbol(else_, "else") => {
+                if i != form.len() - 3 {
+                    return Err(LispError::syntax("else must be the last case clause"));
+                }
+                true
+            }
+            _ => return Err(LispError::syntax("Invalid case clause")),
+        };
+        if matched {
+            return eval_clause_body(clause, key, env, form.clone());
+        }
+    }
+    Ok(Step::Value(LispValue::Unspecified))
+}
+
+/// Evaluates `when` (`expected` is true) or `unless` (`expected` is false).
+fn eval_when(list: &List, env: &Environment, expected: bool) -> Result<Step, LispError> {
+    if list.len() < 2 {
+        let form = if expected { "when" } else { "unless" };
+        return Err(LispError::syntax(format!("Invalid {} expression", form)));
+    }
+    let pending = Pending::When {
+        form: list.clone(),
+        env: env.clone(),
+        expected,
+    };
+    Ok(then(pending, &list[1], env))
+}
+
+/// An `include` that only appeared once the program ran, such as one a
+/// macro produced. Those read in with the program were expanded by `read`.
+fn eval_include(list: &List, env: &Environment) -> Result<Step, LispError> {
+    Ok(Step::Eval(include(list)?, env.clone()))
+}
+
+thread_local! {
+    /// The files being included, innermost last, used to catch files that
+    /// include themselves.
+    static INCLUDING: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Replaces each `include` in `expr` with the forms of the files it names,
+/// so that they are read once, before the program runs. Quoted data is
+/// left alone.
+fn expand_includes(expr: &LispExpression) -> Result<LispExpression, LispError> {
+    let LispExpression::List(list) = expr else {
+        return Ok(expr.clone());
+    };
+    match list.first() {
+        Some(LispExpression::Symbol(s)) if s == "quote" || s == "quasiquote" || s == "syntax" => {
+            Ok(expr.clone())
+        }
+        Some(LispExpression::Symbol(s)) if s == "include" => {
+            include(list).map_err(|err| err.in_form(expr))
+        }
+        _ => Ok(LispExpression::List(List {
+            items: list.iter().map(expand_includes).collect::<Result<_, _>>()?,
+            span: list.span.clone(),
+        })),
+    }
+}
+
+/// `(include "file" ...)` becomes a `begin` of the forms of each file, as if
+/// they were written in its place. Relative paths are resolved against the
+/// directory of the file the `include` itself is in.
+fn include(list: &List) -> Result<LispExpression, LispError> {
+    let base = list
+        .span
+        .as_ref()
+        .and_then(|span| Path::new(&span.source.name).parent().map(Path::to_path_buf));
+    let mut forms = vec![LispExpression::Symbol(Identifier::from("begin"))];
+    for arg in &list[1..] {
+        let path = match arg {
+            LispExpression::String(path) => Path::new(&**path),
+            _ => return Err(LispError::syntax("Invalid include expression")),
+        };
+        let path = match &base {
+            Some(base) => base.join(path),
+            None => path.to_path_buf(),
+        };
+        let text = fs::read_to_string(&path).map_err(|err| {
+            LispError::runtime(format!("cannot read {}: {}", path.display(), err))
+        })?;
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if INCLUDING.with(|including| including.borrow().contains(&canonical)) {
+            return Err(LispError::runtime(format!(
+                "circular include of {}",
+                canonical.display()
+            )));
+        }
+        INCLUDING.with(|including| including.borrow_mut().push(canonical));
+        let result = read(&path.display().to_string(), &text);
+        INCLUDING.with(|including| including.borrow_mut().pop());
+        forms.extend(result?);
+    }
+    Ok(LispExpression::List(List {
+        items: forms.into(),
+        span: list.span.clone(),
+    }))
+}
+
+/// Evaluates the operands of `and` from `form[index]` on, stopping at the
+/// first false one.
+fn eval_and(form: List, index: usize, env: &Environment) -> Step {
+    match form.len() - index {
+        0 => Step::Value(LispValue::Boolean(true)),
+        1 => Step::Eval(form[index].clone(), env.clone()),
+        _ => {
+            let expr = form[index].clone();
+            let pending = Pending::And {
+                form,
+                index: index + 1,
+                env: env.clone(),
+            };
+            then(pending, &expr, env)
+        }
+    }
+}
+
+/// Evaluates the operands of `or` from `form[index]` on, stopping at the
+/// first true one.
+fn eval_or(form: List, index: usize, env: &Environment) -> Step {
+    match form.len() - index {
+        0 => Step::Value(LispValue::Boolean(false)),
+        1 => Step::Eval(form[index].clone(), env.clone()),
+        _ => {
+            let expr = form[index].clone();
+            let pending = Pending::Or {
+                form,
+                index: index + 1,
+                env: env.clone(),
+            };
+            then(pending, &expr, env)
+        }
+    }
+}
+
+/// Parses every top-level form in `tokens`.
+fn parse(tokens: &[Token]) -> Result<Vec<LispExpression>, LispError> {
+    let mut tokens = tokens.iter().peekable();
+    let mut exprs = Vec::new();
+    while tokens.peek().is_some() {
+        exprs.push(parse_tokens(&mut tokens)?);
+    }
+    Ok(exprs)
+}
+
+fn parse_tokens(tokens: &mut Tokens) -> Result<LispExpression, LispError> {
+    let token = match tokens.next() {
+        Some(token) => token,
+        None => return Err(LispError::syntax("Unexpected end of input")),
+    };
+
+    match &token.kind {
+        TokenKind::LeftParen => parse_list(token, tokens),
+        TokenKind::RightParen => Err(LispError::syntax("Unexpected ')'").with_span(&token.span)),
+        TokenKind::Atom(atom) => {
+            match atom.as_str() {
+                "true" | "#t" => Ok(LispExpression::Boolean(true)),
+                "false" | "#f" => Ok(LispExpression::Boolean(false)),
+                _ if atom.starts_with("#\\") => match parse_char(&atom[2..]) {
+                    Some(c) => Ok(LispExpression::Char(c)),
+                    None => Err(LispError::syntax(format!("Unknown character {}", atom))
+                        .with_span(&token.span)),
+                },
+                _ => {
+                    if let Some(num) = Number::parse(atom) {
+                        Ok(LispExpression::Number(num))
+                    } else {
+                        Ok(LispExpression::Symbol(Identifier {
+                            name: atom.clone(),
+                            span: Some(token.span.clone()),
+                            rename: None,
+                        }))
+                    }
+                }
+            }
+        }
+        TokenKind::String(string) => Ok(LispExpression::String(string.as_str().into())),
+        TokenKind::Quote => parse_quoted("quote", token, tokens),
+        TokenKind::Quasiquote => parse_quoted("quasiquote", token, tokens),
+        TokenKind::Unquote => parse_quoted("unquote", token, tokens),
+        TokenKind::UnquoteSplicing => parse_quoted("unquote-splicing", token, tokens),
+        TokenKind::Syntax => parse_quoted("syntax", token, tokens),
+    }
+}
+
+/// Decodes the part of a `#\` character literal after the backslash: a
+/// single character, a name like `space`, or a hex code like `x3bb`.
+fn parse_char(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    match name {
+        "space" => Some(' '),
+        "newline" | "linefeed" => Some('\n'),
+        "tab" => Some('\t'),
+        "return" => Some('\r'),
+        "null" | "nul" => Some('\0'),
+        "alarm" => Some('\u{7}'),
+        "backspace" => Some('\u{8}'),
+        "escape" => Some('\u{1b}'),
+        "delete" => Some('\u{7f}'),
+        _ => {
+            let hex = name.strip_prefix('x')?;
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        }
+    }
+}
+
+/// Expands reader shorthand such as `'x` into `(quote x)`.
+fn parse_quoted(
+    form: &str,
+    quote: &Token,
+    tokens: &mut Tokens,
+) -> Result<LispExpression, LispError> {
+    let datum = parse_tokens(tokens)?;
+    let span = match datum.span() {
+        Some(end) => quote.span.to(end),
+        None => quote.span.clone(),
+    };
+    Ok(LispExpression::List(List {
+        items: vec![LispExpression::Symbol(form.into()), datum].into(),
+        span: Some(span),
+    }))
+}
+
+/// Parses the rest of a list whose opening parenthesis was `open`.
+fn parse_list(open: &Token, tokens: &mut Tokens) -> Result<LispExpression, LispError> {
+    let mut list = Vec::new();
+    let unclosed =
+        || LispError::syntax("Unexpected end of input inside list").with_span(&open.span);
+
+    loop {
+        match tokens.peek().copied() {
+            Some(token) if token.kind == TokenKind::RightParen => {
+                tokens.next();
+                return Ok(LispExpression::List(List {
+                    items: list.into(),
+                    span: Some(open.span.to(&token.span)),
+                }));
+            }
+            Some(token) if matches!(&token.kind, TokenKind::Atom(atom) if atom == ".") => {
+                tokens.next();
+                if list.is_empty() {
+                    return Err(LispError::syntax("Unexpected '.'").with_span(&token.span));
+                }
+                let tail = parse_tokens(tokens)?;
+                return match tokens.next() {
+                    Some(end) if end.kind == TokenKind::RightParen => {
+                        Ok(LispExpression::DottedList(list.into(), Box::new(tail)))
+                    }
+                    Some(end) => {
+                        Err(LispError::syntax("Expected ')' after dotted tail")
+                            .with_span(&end.span))
+                    }
+                    None => Err(unclosed()),
+                };
+            }
+            Some(_) => list.push(parse_tokens(tokens)?),
+            None => return Err(unclosed()),
+        }
+    }
+}
+
+/// Reads every form in `text`, whose spans will refer to it as `name`, with
+/// the files it includes spliced in.
+fn read(name: &str, text: &str) -> Result<Vec<LispExpression>, LispError> {
+    let source = Rc::new(Source {
+        name: name.to_string(),
+        text: text.to_string(),
+    });
+    parse(&lexer::tokenize(&source)?)?
+        .iter()
+        .map(expand_includes)
+        .collect()
+}
+
+/// Evaluates every form in `text`, returning the value of the last one.
+/// A leading `#!` line is skipped so scripts can be made executable; its
+/// newline is kept so line numbers stay right.
+fn run(name: &str, text: &str, env: &Environment) -> Result<LispValue, LispError> {
+    let text = match text.strip_prefix("#!") {
+        Some(rest) => &rest[rest.find('\n').unwrap_or(rest.len())..],
+        None => text,
+    };
+    let exprs = read(name, text)?;
+    let mut result = LispValue::Unspecified;
+    for expr in exprs {
+        result = eval(&expr, env)?;
+    }
+    Ok(result)
+}
+
+/// Exits with a non-zero status if a whole program failed.
+fn exit_on_error(result: Result<LispValue, LispError>) {
+    if let Err(err) = result {
+        eprintln!("{}", err.render());
+        process::exit(1);
+    }
+}
+
+fn repl(env: &Environment) {
+    let mut input = String::new();
+    loop {
+        print!("{}", if input.is_empty() { "> " } else { ".. " });
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap() == 0 {
+            println!();
+            break;
+        }
+
+        if input.is_empty() && line.trim() == "exit" {
+            break;
+        }
+
+        // Keep reading continuation lines until the input is balanced.
+        input.push_str(&line);
+        if lexer::is_incomplete(&input) {
+            continue;
+        }
+        let source = std::mem::take(&mut input);
+
+        match read("<repl>", &source) {
+            Ok(exprs) => {
+                for expr in exprs {
+                    match eval(&expr, env) {
+                        Ok(LispValue::Unspecified) => {}
+                        Ok(value) => println!("{}", value),
+                        Err(err) => {
+                            eprintln!("{}", err.render());
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => eprintln!("{}", err.render()),
         }
     }
 }
+
+const USAGE: &str = "usage: lisp-in-rust [-e EXPR | FILE [ARG...]]";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let env = Environment::standard();
+
+    match args.get(1).map(String::as_str) {
+        Some("-e") => {
+            let Some(expr) = args.get(2) else {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            };
+            builtins::set_command_line(args[..1].iter().chain(&args[3..]).cloned().collect());
+            match run("<expr>", expr, &env) {
+                Ok(LispValue::Unspecified) => {}
+                Ok(value) => println!("{}", value),
+                Err(err) => {
+                    eprintln!("{}", err.render());
+                    process::exit(1);
+                }
+            }
+        }
+        Some("-h" | "--help") => println!("{}", USAGE),
+        Some(path) => {
+            builtins::set_command_line(args[1..].to_vec());
+            exit_on_error(builtins::load(path, &env));
+        }
+        None => {
+            builtins::set_command_line(args);
+            if io::stdin().is_terminal() {
+                repl(&env);
+            } else {
+                let mut source = String::new();
+                if let Err(err) = io::stdin().read_to_string(&mut source) {
+                    eprintln!("stdin: {}", err);
+                    process::exit(1);
+                }
+                exit_on_error(run("<stdin>", &source, &env));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates `source` in a fresh environment and returns its value as
+    /// `write` would print it.
+    fn eval_str(source: &str) -> String {
+        match run("<test>", source, &Environment::standard()) {
+            Ok(value) => value.to_string(),
+            Err(err) => panic!("{}", err.render()),
+        }
+    }
+
+    /// Evaluates `source`, which must fail, and returns the error.
+    fn error_of(source: &str) -> LispError {
+        match run("<test>", source, &Environment::standard()) {
+            Ok(value) => panic!("expected an error, got {}", value),
+            Err(err) => err,
+        }
+    }
+
+    /// Evaluates `source`, which must fail, and returns the error message.
+    fn eval_err(source: &str) -> String {
+        error_of(source).to_string()
+    }
+
+    #[test]
+    fn macro_temporaries_do_not_capture_user_variables() {
+        let swap = "(define-syntax swap!
+                      (syntax-rules ()
+                        ((_ a b) (let ((tmp a)) (set! a b) (set! b tmp)))))";
+        assert_eq!(
+            eval_str(&format!(
+                "{} (define tmp 1) (define other 2) (swap! tmp other) (list tmp other)",
+                swap
+            )),
+            "(2 1)"
+        );
+        let my_or = "(define-syntax my-or
+                       (syntax-rules ()
+                         ((_) #f)
+                         ((_ e) e)
+                         ((_ e r ...) (let ((t e)) (if t t (my-or r ...))))))";
+        assert_eq!(
+            eval_str(&format!("{} (define t 5) (my-or #f t)", my_or)),
+            "5"
+        );
+    }
+
+    #[test]
+    fn macro_references_are_not_captured_at_the_use_site() {
+        assert_eq!(
+            eval_str("(define x 1) (define-syntax get-x (syntax-rules () ((_) x))) (let ((x 2)) (get-x))"),
+            "1"
+        );
+        assert_eq!(
+            eval_str(
+                "(define-syntax my-if (syntax-rules () ((_ c a b) (cond (c a) (else b)))))
+                 (let ((if list) (else #f)) (my-if #f 1 2))"
+            ),
+            "2"
+        );
+    }
+
+    #[test]
+    fn syntax_rules_ellipses() {
+        assert_eq!(
+            eval_str("(define-syntax my-list (syntax-rules ::: () ((_ e :::) (list e :::)))) (my-list 1 2 3)"),
+            "(1 2 3)"
+        );
+        assert_eq!(
+            eval_str(
+                "(define-syntax ell (syntax-rules () ((_ a ...) '((a (... ...)) ...)))) (ell 1 2)"
+            ),
+            "((1 ...) (2 ...))"
+        );
+    }
+
+    #[test]
+    fn syntax_case_breaks_hygiene_with_datum_to_syntax() {
+        assert_eq!(
+            eval_str(
+                "(define-syntax aif
+                   (lambda (stx)
+                     (syntax-case stx ()
+                       ((k c then else)
+                        (with-syntax ((it (datum->syntax (syntax k) 'it)))
+                          (syntax (let ((it c)) (if it then else))))))))
+                 (aif (+ 1 2) (* it 10) 0)"
+            ),
+            "30"
+        );
+    }
+
+    #[test]
+    fn syntax_case_generates_distinct_temporaries() {
+        assert_eq!(
+            eval_str(
+                "(define-syntax bind-all
+                   (lambda (stx)
+                     (syntax-case stx ()
+                       ((_ e ...)
+                        (with-syntax (((t ...) (generate-temporaries (syntax (e ...)))))
+                          (syntax (let ((t e) ...) (list t ...))))))))
+                 (bind-all 1 2 3)"
+            ),
+            "(1 2 3)"
+        );
+    }
+
+    #[test]
+    fn syntax_case_fenders_and_hygiene() {
+        assert_eq!(
+            eval_str(
+                "(define-syntax kind
+                   (lambda (stx)
+                     (syntax-case stx ()
+                       ((_ x) (identifier? (syntax x)) (syntax 'identifier))
+                       ((_ x) (syntax 'other)))))
+                 (list (kind a) (kind 1))"
+            ),
+            "(identifier other)"
+        );
+        assert_eq!(
+            eval_str(
+                "(define-syntax swap!
+                   (lambda (stx)
+                     (syntax-case stx ()
+                       ((_ a b) (syntax (let ((tmp a)) (set! a b) (set! b tmp)))))))
+                 (define tmp 1) (define other 2) (swap! tmp other) (list tmp other)"
+            ),
+            "(2 1)"
+        );
+    }
+
+    #[test]
+    fn continuations_escape() {
+        assert_eq!(eval_str("(+ 1 (call/cc (lambda (k) (* 10 (k 41)))))"), "42");
+        assert_eq!(
+            eval_str(
+                "(define (find-negative lst)
+                   (call/cc
+                     (lambda (return)
+                       (let loop ((l lst))
+                         (cond ((null? l) #f)
+                               ((negative? (car l)) (return (car l)))
+                               (else (loop (cdr l))))))))
+                 (list (find-negative '(1 -2 3)) (find-negative '(1 2)))"
+            ),
+            "(-2 #f)"
+        );
+        // Macro expansion runs the transformer separately; the escape still
+        // reaches the evaluation it belongs to.
+        assert_eq!(
+            eval_str("(+ 100 (call/cc (lambda (out) (define-macro (bail) (out 5)) (bail))))"),
+            "105"
+        );
+    }
+
+    #[test]
+    fn continuations_can_be_reentered() {
+        assert_eq!(
+            eval_str(
+                "(let ((k #f) (n 0))
+                   (let ((r (call/cc (lambda (c) (set! k c) 0))))
+                     (set! n (+ n 1))
+                     (if (< r 3) (k (+ r 1)) (list r n))))"
+            ),
+            "(3 4)"
+        );
+        assert_eq!(
+            eval_str("(call-with-values (lambda () (call/cc (lambda (k) (k 1 2)))) list)"),
+            "(1 2)"
+        );
+        assert_eq!(
+            eval_err("(define saved #f) (define-macro (m) (call/cc (lambda (c) (set! saved c) 1))) (m) (saved 2)"),
+            "continuation can't be resumed once the evaluation it was captured in has finished"
+        );
+    }
+
+    #[test]
+    fn tail_loops_run_in_constant_space() {
+        assert_eq!(
+            eval_str("(let loop ((i 0)) (if (< i 1000000) (loop (+ i 1)) i))"),
+            "1000000"
+        );
+    }
+
+    #[test]
+    fn mutually_recursive_tail_calls_run_in_constant_space() {
+        assert_eq!(
+            eval_str(
+                "(define (even? n) (if (= n 0) #t (odd? (- n 1))))
+                 (define (odd? n) (if (= n 0) #f (even? (- n 1))))
+                 (even? 1000001)"
+            ),
+            "#f"
+        );
+    }
+
+    #[test]
+    fn equal_compares_long_lists_without_recursing() {
+        assert_eq!(
+            eval_str(
+                "(define a (let loop ((i 0) (acc '())) (if (= i 200000) acc (loop (+ i 1) (cons i acc)))))
+                 (define b (reverse (reverse a)))
+                 (define c (append (reverse (cdr (reverse a))) '(x)))
+                 (list (equal? a b) (equal? a c))"
+            ),
+            "(#t #f)"
+        );
+    }
+
+    #[test]
+    fn exact_integers_promote_to_bignums() {
+        assert_eq!(
+            eval_str(
+                "(list (+ 9223372036854775807 1)
+                       (- -9223372036854775808 1)
+                       (* 99999999999 99999999999)
+                       (quotient (expt 10 30) (expt 10 28))
+                       (exact? (expt 2 100)))"
+            ),
+            "(9223372036854775808 -9223372036854775809 9999999999800000000001 100 #t)"
+        );
+        assert_eq!(
+            eval_str("(let loop ((i 1) (acc 1)) (if (> i 25) acc (loop (+ i 1) (* acc i))))"),
+            "15511210043330985984000000"
+        );
+    }
+
+    #[test]
+    fn rationals_stay_exact() {
+        assert_eq!(
+            eval_str(
+                "(list (/ 1 3) (+ 1/3 2/3) (* 2/3 3/4) (/ 6 4) (/ 4 2) (- 1/2 1/2)
+                       (numerator 6/4) (denominator 6/4) (exact? 1/3))"
+            ),
+            "(1/3 1 1/2 3/2 2 0 3 2 #t)"
+        );
+        assert_eq!(
+            eval_str("(list (exact 2.5) (inexact 1/8) (= 1/2 0.5) (eqv? 1/2 0.5) (floor 7/2) (round 5/2))"),
+            "(5/2 0.125 #t #f 3 2)"
+        );
+        assert_eq!(eval_err("(/ 1 0)"), "/: division by zero");
+    }
+
+    #[test]
+    fn exact_integer_sqrt_returns_two_values() {
+        assert_eq!(
+            eval_str("(call-with-values (lambda () (exact-integer-sqrt 17)) list)"),
+            "(4 1)"
+        );
+        assert_eq!(
+            eval_str("(call-with-values (lambda () (values 1 2 3)) +)"),
+            "6"
+        );
+    }
+
+    #[test]
+    fn exact_powers_stay_exact_or_fail() {
+        assert_eq!(
+            eval_str("(list (expt 2 100) (expt 2/3 -2) (expt -1 1000000000001) (expt 2.0 0.5))"),
+            "(1267650600228229401496703205376 9/4 -1 1.4142135623730951)"
+        );
+        assert_eq!(
+            eval_err("(expt 2 1000000000000)"),
+            "expt: exponent too large"
+        );
+    }
+
+    #[test]
+    fn cycles_print_with_datum_labels() {
+        assert_eq!(
+            eval_str("(define l (list 1 2 3)) (set-cdr! (cdr (cdr l)) l) l"),
+            "#0=(1 2 3 . #0#)"
+        );
+        assert_eq!(
+            eval_str("(define p (list 1 2)) (set-car! (cdr p) p) p"),
+            "#0=(1 #0#)"
+        );
+        assert_eq!(
+            eval_str("(define a (list 1 2)) (list a a)"),
+            "((1 2) (1 2))"
+        );
+    }
+
+    /// Writes `files` into a fresh directory named after `test` and returns
+    /// the directory.
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("lisp-in-rust-{}-{}", test, process::id()));
+        for (name, text) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        dir
+    }
+
+    fn load_form(path: &Path) -> String {
+        format!("(load {:?})", path.display().to_string())
+    }
+
+    #[test]
+    fn include_resolves_paths_against_the_including_file() {
+        let dir = write_files(
+            "include-paths",
+            &[
+                ("main.lisp", "(include \"lib/a.lisp\") (list a b)"),
+                ("lib/a.lisp", "(define a 1) (include \"b.lisp\")"),
+                ("lib/b.lisp", "(define b 2)"),
+            ],
+        );
+        assert_eq!(eval_str(&load_form(&dir.join("main.lisp"))), "(1 2)");
+    }
+
+    #[test]
+    fn include_in_a_procedure_body_is_read_once() {
+        let dir = write_files(
+            "include-body",
+            &[
+                ("main.lisp", "(define (f x) (include \"body.lisp\"))"),
+                ("body.lisp", "(+ x 1)"),
+            ],
+        );
+        let env = Environment::standard();
+        run("<test>", &load_form(&dir.join("main.lisp")), &env).unwrap();
+        fs::remove_file(dir.join("body.lisp")).unwrap();
+        assert_eq!(
+            run("<test>", "(list (f 1) (f 2))", &env)
+                .unwrap()
+                .to_string(),
+            "(2 3)"
+        );
+
+        let err = eval_err(&format!(
+            "(define (g) (include {:?})) 1",
+            dir.join("body.lisp").display().to_string()
+        ));
+        assert!(err.starts_with("cannot read"), "{}", err);
+    }
+
+    #[test]
+    fn circular_includes_and_loads_are_errors() {
+        let dir = write_files(
+            "circular",
+            &[
+                ("inc1.lisp", "(include \"inc2.lisp\")"),
+                ("inc2.lisp", "(include \"inc1.lisp\")"),
+                ("load1.lisp", "(load \"load2.lisp\")"),
+                ("load2.lisp", "(load \"load1.lisp\")"),
+            ],
+        );
+        let err = eval_err(&load_form(&dir.join("inc1.lisp")));
+        assert!(err.starts_with("circular include of"), "{}", err);
+        let err = eval_err(&load_form(&dir.join("load1.lisp")));
+        assert!(err.starts_with("circular load of"), "{}", err);
+    }
+
+    #[test]
+    fn errors_point_at_the_failing_code() {
+        assert_eq!(
+            error_of("(define x 1)\n(+ x\n   (car\n     y))").render(),
+            "Error: unbound variable: y\n --> <test>:4:6\n  |\n4 |      y))\n  |      ^"
+        );
+        // A form spanning several lines is underlined to the end of its
+        // first line.
+        assert_eq!(
+            error_of("(define (f a) a)\n(f 1\n   2)").render(),
+            "Error: #<procedure f>: expected 1 argument(s), got 2\n --> <test>:2:1\n  |\n2 | (f 1\n  | ^^^^\n\
+             Backtrace (most recent call first):\n  f at <test>:2:1"
+        );
+        assert_eq!(
+            error_of("(list 1\n  (if 1 2 3 4))").render(),
+            "Error: Invalid if expression\n --> <test>:2:3\n  |\n2 |   (if 1 2 3 4))\n  |   ^^^^^^^^^^^^"
+        );
+        assert_eq!(
+            error_of("(display 1)\n(foo").render(),
+            "Error: Unexpected end of input inside list\n --> <test>:2:1\n  |\n2 | (foo\n  | ^"
+        );
+    }
+
+    #[test]
+    fn errors_come_back_as_their_kind() {
+        assert!(matches!(
+            error_of("(car y)"),
+            LispError::UnboundVariable { .. }
+        ));
+        assert!(matches!(
+            error_of("(define (f a) a) (f 1 2)"),
+            LispError::Arity { .. }
+        ));
+        assert!(matches!(error_of("(car 1 2)"), LispError::Arity { .. }));
+        assert!(matches!(error_of("(+ 1 \"a\")"), LispError::Type { .. }));
+        assert!(matches!(error_of("(if)"), LispError::Syntax { .. }));
+        assert!(matches!(error_of("(1 2"), LispError::Syntax { .. }));
+    }
+
+    #[test]
+    fn backtraces_drop_callers_replaced_by_tail_calls() {
+        let procedures = "(define (c) (backtrace))\n(define (b) (list (c)))\n";
+        assert_eq!(
+            eval_str(&format!("{}(define (a) (b))\n(a)", procedures)),
+            r#"(("c at <test>:2:19" "b at <test>:3:13"))"#
+        );
+        assert_eq!(
+            eval_str(&format!("{}(define (a) (list (b)))\n(a)", procedures)),
+            r#"((("c at <test>:2:19" "b at <test>:3:19" "a at <test>:4:1")))"#
+        );
+    }
+
+    #[test]
+    fn backtraces_in_handlers_list_the_raising_calls() {
+        let procedures = "(define (c x) (raise x))\n(define (b) (+ 1 (c 5)))\n";
+        assert_eq!(
+            eval_str(&format!("{}(guard (e (#t (backtrace))) (b))", procedures)),
+            r#"("raise at <test>:1:15" "c at <test>:2:18" "b at <test>:3:29")"#
+        );
+        assert_eq!(
+            eval_str(&format!(
+                "{}(call/cc (lambda (k) (with-exception-handler (lambda (e) (k (backtrace))) b)))",
+                procedures
+            )),
+            r#"("raise at <test>:1:15" "c at <test>:2:18" "b at <test>:3:22" "<anonymous> at <test>:3:1")"#
+        );
+    }
+
+    #[test]
+    fn exception_handlers_return_to_raise_continuable() {
+        assert_eq!(
+            eval_str("(+ 1 (with-exception-handler (lambda (e) (* e 10)) (lambda () (+ 1 (raise-continuable 5)))))"),
+            "52"
+        );
+        // The inner handler runs with only the outer one installed.
+        assert_eq!(
+            eval_str(
+                "(with-exception-handler
+                   (lambda (e) (* e 2))
+                   (lambda ()
+                     (with-exception-handler
+                       (lambda (e) (+ (raise-continuable (+ e 1)) 100))
+                       (lambda () (raise-continuable 1)))))"
+            ),
+            "104"
+        );
+    }
+
+    #[test]
+    fn exception_handlers_must_not_return_from_raise() {
+        assert_eq!(
+            eval_err("(with-exception-handler (lambda (e) 0) (lambda () (+ 1 (raise 'oops))))"),
+            "exception handler returned from a non-continuable exception oops"
+        );
+        assert_eq!(
+            eval_str(
+                "(guard (e ((error-object? e) (error-object-message e)))
+                   (with-exception-handler (lambda (e) 0) (lambda () (raise 'oops))))"
+            ),
+            r#""exception handler returned from a non-continuable exception""#
+        );
+    }
+
+    #[test]
+    fn macroexpand_1_expands_once_and_macroexpand_repeatedly() {
+        assert_eq!(
+            eval_str(
+                "(define-macro (m x) (list '+ x 1))
+                 (define-macro (m2 x) (list 'm x))
+                 (list (macroexpand-1 '(m2 2)) (macroexpand '(m2 2)) (macroexpand-1 '(f 2)) (m2 2))"
+            ),
+            "((m 2) (+ 2 1) (f 2) 3)"
+        );
+    }
+
+    #[test]
+    fn define_macro_takes_a_rest_parameter() {
+        assert_eq!(
+            eval_str(
+                "(define-macro (while test . body) `(let loop () (when ,test ,@body (loop))))
+                 (define i 0)
+                 (define acc '())
+                 (while (< i 3) (set! acc (cons i acc)) (set! i (+ i 1)))
+                 (list acc (macroexpand-1 '(while c a b)))"
+            ),
+            "((2 1 0) (let loop () (when c a b (loop))))"
+        );
+    }
+}
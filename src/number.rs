@@ -0,0 +1,760 @@
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_integer::Integer as _;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+/// A Scheme number. Exact integers live in an `i64` until an operation
+/// overflows, at which point they are promoted to a `BigInt`; results that
+/// fit in an `i64` again are demoted, so `Integer` and `BigInteger` never
+/// overlap. `Rational` holds exact fractions in lowest terms whose
+/// denominator is never 1. `Real` is the inexact kind, and `Complex` holds
+/// inexact complex numbers whose imaginary part is never zero.
+#[derive(Debug, Clone)]
+pub enum Number {
+    Integer(i64),
+    BigInteger(Rc<BigInt>),
+    Rational(Rc<BigRational>),
+    Real(f64),
+    Complex(Complex64),
+}
+
+/// Both operands of a binary operation converted to their common kind.
+enum Operands {
+    Integers(i64, i64),
+    BigIntegers(BigInt, BigInt),
+    Rationals(BigRational, BigRational),
+    Reals(f64, f64),
+    Complexes(Complex64, Complex64),
+}
+
+impl Number {
+    pub fn from_big(n: BigInt) -> Number {
+        match n.to_i64() {
+            Some(i) => Number::Integer(i),
+            None => Number::BigInteger(Rc::new(n)),
+        }
+    }
+
+    pub fn from_rational(r: BigRational) -> Number {
+        if r.is_integer() {
+            Number::from_big(r.to_integer())
+        } else {
+            Number::Rational(Rc::new(r))
+        }
+    }
+
+    pub fn from_complex(c: Complex64) -> Number {
+        if c.im == 0.0 {
+            Number::Real(c.re)
+        } else {
+            Number::Complex(c)
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Number::Real(_) | Number::Complex(_))
+    }
+
+    pub fn is_real(&self) -> bool {
+        !matches!(self, Number::Complex(_))
+    }
+
+    /// True for exact integers and for inexact reals without a fraction.
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Integer(_) | Number::BigInteger(_) => true,
+            Number::Rational(_) | Number::Complex(_) => false,
+            Number::Real(r) => r.is_finite() && r.fract() == 0.0,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(i) => *i as f64,
+            Number::BigInteger(b) => b.to_f64().unwrap_or(f64::NAN),
+            Number::Rational(r) => r.to_f64().unwrap_or(f64::NAN),
+            Number::Real(r) => *r,
+            Number::Complex(_) => f64::NAN,
+        }
+    }
+
+    pub fn to_complex(&self) -> Complex64 {
+        match self {
+            Number::Complex(c) => *c,
+            real => Complex64::new(real.to_f64(), 0.0),
+        }
+    }
+
+    fn to_big(&self) -> Option<BigInt> {
+        match self {
+            Number::Integer(i) => Some(BigInt::from(*i)),
+            Number::BigInteger(b) => Some((**b).clone()),
+            Number::Rational(_) | Number::Real(_) | Number::Complex(_) => None,
+        }
+    }
+
+    fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Number::Rational(r) => Some((**r).clone()),
+            Number::Real(_) | Number::Complex(_) => None,
+            integer => integer.to_big().map(BigRational::from_integer),
+        }
+    }
+
+    pub fn to_inexact(&self) -> Number {
+        match self {
+            Number::Complex(c) => Number::Complex(*c),
+            real => Number::Real(real.to_f64()),
+        }
+    }
+
+    /// Converts to the exact number with the same value; every finite
+    /// float is a binary fraction, so only infinities and NaN fail.
+    pub fn to_exact(&self) -> Result<Number, String> {
+        match self {
+            Number::Real(r) => match BigRational::from_float(*r) {
+                Some(exact) => Ok(Number::from_rational(exact)),
+                None => Err(format!("no exact representation for {}", self)),
+            },
+            Number::Complex(_) => Err(format!("no exact representation for {}", self)),
+            exact => Ok(exact.clone()),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Integer(i) => *i == 0,
+            Number::BigInteger(_) | Number::Rational(_) | Number::Complex(_) => false,
+            Number::Real(r) => *r == 0.0,
+        }
+    }
+
+    fn operands(&self, other: &Number) -> Operands {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b)) => Operands::Integers(*a, *b),
+            (Number::Complex(_), _) | (_, Number::Complex(_)) => {
+                Operands::Complexes(self.to_complex(), other.to_complex())
+            }
+            (a, b) => match (a.to_big(), b.to_big()) {
+                (Some(a), Some(b)) => Operands::BigIntegers(a, b),
+                _ => match (a.to_rational(), b.to_rational()) {
+                    (Some(a), Some(b)) => Operands::Rationals(a, b),
+                    _ => Operands::Reals(a.to_f64(), b.to_f64()),
+                },
+            },
+        }
+    }
+
+    /// Applies an operation that cannot fail, falling back from `i64` to
+    /// `BigInt` when the fixnum version overflows.
+    fn arithmetic(
+        &self,
+        other: &Number,
+        fixnum: fn(i64, i64) -> Option<i64>,
+        bignum: fn(BigInt, BigInt) -> BigInt,
+        rational: fn(BigRational, BigRational) -> BigRational,
+        real: fn(f64, f64) -> f64,
+        complex: fn(Complex64, Complex64) -> Complex64,
+    ) -> Number {
+        match self.operands(other) {
+            Operands::Integers(a, b) => match fixnum(a, b) {
+                Some(result) => Number::Integer(result),
+                None => Number::from_big(bignum(BigInt::from(a), BigInt::from(b))),
+            },
+            Operands::BigIntegers(a, b) => Number::from_big(bignum(a, b)),
+            Operands::Rationals(a, b) => Number::from_rational(rational(a, b)),
+            Operands::Reals(a, b) => Number::Real(real(a, b)),
+            Operands::Complexes(a, b) => Number::from_complex(complex(a, b)),
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> Number {
+        self.arithmetic(
+            other,
+            i64::checked_add,
+            |a, b| a + b,
+            |a, b| a + b,
+            |a, b| a + b,
+            |a, b| a + b,
+        )
+    }
+
+    pub fn sub(&self, other: &Number) -> Number {
+        self.arithmetic(
+            other,
+            i64::checked_sub,
+            |a, b| a - b,
+            |a, b| a - b,
+            |a, b| a - b,
+            |a, b| a - b,
+        )
+    }
+
+    pub fn mul(&self, other: &Number) -> Number {
+        self.arithmetic(
+            other,
+            i64::checked_mul,
+            |a, b| a * b,
+            |a, b| a * b,
+            |a, b| a * b,
+            |a, b| a * b,
+        )
+    }
+
+    /// Division of exact numbers is exact, producing a rational when the
+    /// divisor does not divide evenly.
+    pub fn div(&self, other: &Number) -> Result<Number, String> {
+        if other.is_exact() && other.is_zero() {
+            return Err("division by zero".to_string());
+        }
+        match self.operands(other) {
+            Operands::Integers(a, b) if a.checked_rem(b) == Some(0) => Ok(Number::Integer(a / b)),
+            Operands::Reals(a, b) => Ok(Number::Real(a / b)),
+            Operands::Complexes(a, b) => Ok(Number::from_complex(a / b)),
+            _ => {
+                let (a, b) = (self.to_rational(), other.to_rational());
+                Ok(Number::from_rational(
+                    a.unwrap_or_default() / b.unwrap_or_default(),
+                ))
+            }
+        }
+    }
+
+    pub fn neg(&self) -> Number {
+        Number::Integer(0).sub(self)
+    }
+
+    pub fn abs(&self) -> Number {
+        if self.is_negative() {
+            self.neg()
+        } else {
+            self.clone()
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Number::Integer(i) => *i < 0,
+            Number::BigInteger(b) => b.is_negative(),
+            Number::Rational(r) => r.is_negative(),
+            Number::Real(r) => *r < 0.0,
+            Number::Complex(_) => false,
+        }
+    }
+
+    pub fn is_positive(&self) -> bool {
+        match self {
+            Number::Integer(i) => *i > 0,
+            Number::BigInteger(b) => b.is_positive(),
+            Number::Rational(r) => r.is_positive(),
+            Number::Real(r) => *r > 0.0,
+            Number::Complex(_) => false,
+        }
+    }
+
+    /// Numeric comparison across exactness; `None` when either side is NaN
+    /// or complex.
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match self.operands(other) {
+            Operands::Integers(a, b) => Some(a.cmp(&b)),
+            Operands::BigIntegers(a, b) => Some(a.cmp(&b)),
+            Operands::Rationals(a, b) => Some(a.cmp(&b)),
+            Operands::Reals(a, b) => a.partial_cmp(&b),
+            Operands::Complexes(_, _) => None,
+        }
+    }
+
+    /// Numeric equality, which unlike `compare` is defined for complex numbers.
+    pub fn equals(&self, other: &Number) -> bool {
+        match self.operands(other) {
+            Operands::Complexes(a, b) => a == b,
+            _ => self.compare(other) == Some(Ordering::Equal),
+        }
+    }
+
+    /// `eqv?` on numbers: equal value and the same exactness.
+    pub fn eqv(&self, other: &Number) -> bool {
+        self.is_exact() == other.is_exact() && self.equals(other)
+    }
+
+    /// Shared implementation of `quotient`, `remainder` and `modulo`.
+    fn integer_division(
+        &self,
+        other: &Number,
+        bignum: fn(&BigInt, &BigInt) -> BigInt,
+        real: fn(f64, f64) -> f64,
+    ) -> Result<Number, String> {
+        if !self.is_integer() || !other.is_integer() {
+            return Err("expected integer".to_string());
+        }
+        if other.is_zero() {
+            return Err("division by zero".to_string());
+        }
+        match self.operands(other) {
+            Operands::Integers(a, b) => {
+                Ok(Number::from_big(bignum(&BigInt::from(a), &BigInt::from(b))))
+            }
+            Operands::BigIntegers(a, b) => Ok(Number::from_big(bignum(&a, &b))),
+            Operands::Reals(a, b) => Ok(Number::Real(real(a, b))),
+            Operands::Rationals(_, _) | Operands::Complexes(_, _) => {
+                unreachable!("integer operands are never rationals or complex")
+            }
+        }
+    }
+
+    pub fn quotient(&self, other: &Number) -> Result<Number, String> {
+        self.integer_division(other, |a, b| a / b, |a, b| (a / b).trunc())
+    }
+
+    pub fn remainder(&self, other: &Number) -> Result<Number, String> {
+        self.integer_division(other, |a, b| a % b, |a, b| a % b)
+    }
+
+    pub fn modulo(&self, other: &Number) -> Result<Number, String> {
+        self.integer_division(other, |a, b| a.mod_floor(b), |a, b| a - b * (a / b).floor())
+    }
+
+    /// The numerator and denominator of the number in lowest terms; inexact
+    /// numbers give inexact results.
+    pub fn fraction(&self) -> Result<(Number, Number), String> {
+        let exact = match self.to_exact()? {
+            Number::Rational(r) => (*r).clone(),
+            exact => exact.to_rational().unwrap_or_default(),
+        };
+        let numerator = Number::from_big(exact.numer().clone());
+        let denominator = Number::from_big(exact.denom().clone());
+        if self.is_exact() {
+            Ok((numerator, denominator))
+        } else {
+            Ok((numerator.to_inexact(), denominator.to_inexact()))
+        }
+    }
+
+    /// Shared implementation of `floor`, `ceiling`, `truncate` and `round`.
+    fn to_integer(
+        &self,
+        rational: fn(&BigRational) -> BigRational,
+        real: fn(f64) -> f64,
+    ) -> Number {
+        match self {
+            Number::Rational(r) => Number::from_rational(rational(r)),
+            Number::Real(r) => Number::Real(real(*r)),
+            integer => integer.clone(),
+        }
+    }
+
+    pub fn floor(&self) -> Number {
+        self.to_integer(BigRational::floor, f64::floor)
+    }
+
+    pub fn ceiling(&self) -> Number {
+        self.to_integer(BigRational::ceil, f64::ceil)
+    }
+
+    pub fn truncate(&self) -> Number {
+        self.to_integer(BigRational::trunc, f64::trunc)
+    }
+
+    /// Rounds to the nearest integer, choosing the even one on ties.
+    pub fn round(&self) -> Number {
+        self.to_integer(
+            |r| {
+                let half = BigRational::new(BigInt::one(), BigInt::from(2));
+                let rounded = (r + &half).floor();
+                if &rounded - r == half && rounded.to_integer().is_odd() {
+                    rounded - BigRational::one()
+                } else {
+                    rounded
+                }
+            },
+            f64::round_ties_even,
+        )
+    }
+
+    /// The simplest rational within `tolerance` of `self`.
+    pub fn rationalize(&self, tolerance: &Number) -> Result<Number, String> {
+        let exact = |n: &Number| n.to_exact().map(|n| n.to_rational().unwrap_or_default());
+        let (x, y) = (exact(self)?, exact(tolerance)?.abs());
+        let result = Number::from_rational(simplest_between(&(&x - &y), &(&x + &y)));
+        if self.is_exact() && tolerance.is_exact() {
+            Ok(result)
+        } else {
+            Ok(result.to_inexact())
+        }
+    }
+
+    /// Applies a function that stays real on `domain` and otherwise moves
+    /// into the complex plane, like `sqrt` of a negative number.
+    fn transcendental(
+        &self,
+        domain: fn(f64) -> bool,
+        real: fn(f64) -> f64,
+        complex: fn(Complex64) -> Complex64,
+    ) -> Number {
+        match self {
+            Number::Complex(c) => Number::from_complex(complex(*c)),
+            x if domain(x.to_f64()) => Number::Real(real(x.to_f64())),
+            x => Number::from_complex(complex(x.to_complex())),
+        }
+    }
+
+    pub fn exp(&self) -> Number {
+        self.transcendental(|_| true, f64::exp, Complex64::exp)
+    }
+
+    pub fn ln(&self) -> Number {
+        self.transcendental(|x| x >= 0.0 || x.is_nan(), f64::ln, Complex64::ln)
+    }
+
+    pub fn sin(&self) -> Number {
+        self.transcendental(|_| true, f64::sin, Complex64::sin)
+    }
+
+    pub fn cos(&self) -> Number {
+        self.transcendental(|_| true, f64::cos, Complex64::cos)
+    }
+
+    pub fn tan(&self) -> Number {
+        self.transcendental(|_| true, f64::tan, Complex64::tan)
+    }
+
+    pub fn asin(&self) -> Number {
+        self.transcendental(
+            |x| x.is_nan() || (-1.0..=1.0).contains(&x),
+            f64::asin,
+            Complex64::asin,
+        )
+    }
+
+    pub fn acos(&self) -> Number {
+        self.transcendental(
+            |x| x.is_nan() || (-1.0..=1.0).contains(&x),
+            f64::acos,
+            Complex64::acos,
+        )
+    }
+
+    pub fn atan(&self) -> Number {
+        self.transcendental(|_| true, f64::atan, Complex64::atan)
+    }
+
+    /// Square roots of exact squares such as `16` or `4/9` stay exact.
+    pub fn sqrt(&self) -> Number {
+        if let Some(r) = self.to_rational().filter(|r| !r.is_negative()) {
+            let (numer, denom) = (r.numer().sqrt(), r.denom().sqrt());
+            if BigRational::new(&numer * &numer, &denom * &denom) == r {
+                return Number::from_rational(BigRational::new(numer, denom));
+            }
+        }
+        self.transcendental(|x| x >= 0.0 || x.is_nan(), f64::sqrt, Complex64::sqrt)
+    }
+
+    /// Raises to a power, exactly when the base is exact and the exponent is
+    /// an exact integer.
+    pub fn expt(&self, power: &Number) -> Result<Number, String> {
+        let exponent = match power {
+            Number::Integer(i) => i32::try_from(*i).ok(),
+            _ => None,
+        };
+        if let (Some(base), Some(exponent)) = (self.to_rational(), exponent) {
+            if base.is_zero() && exponent < 0 {
+                return Err("division by zero".to_string());
+            }
+            return Ok(Number::from_rational(num_traits::pow::Pow::pow(
+                base, exponent,
+            )));
+        }
+        // An exact power with a larger exponent wouldn't fit in memory,
+        // unless the base is 0, 1 or -1.
+        if let (Some(base), Some(exponent)) = (self.to_rational(), power.to_big()) {
+            return if base.is_zero() && exponent.is_negative() {
+                Err("division by zero".to_string())
+            } else if base.is_zero() {
+                Ok(Number::Integer(0))
+            } else if base.is_one() {
+                Ok(Number::Integer(1))
+            } else if (-base).is_one() {
+                Ok(Number::Integer(if exponent.is_even() { 1 } else { -1 }))
+            } else {
+                Err("exponent too large".to_string())
+            };
+        }
+        if self.is_real() && power.is_real() {
+            let (base, exponent) = (self.to_f64(), power.to_f64());
+            if base >= 0.0 || exponent.fract() == 0.0 || !exponent.is_finite() {
+                return Ok(Number::Real(base.powf(exponent)));
+            }
+        }
+        if self.is_zero() {
+            return Ok(if power.is_zero() {
+                Number::Integer(1)
+            } else {
+                self.to_inexact()
+            });
+        }
+        Ok(Number::from_complex(
+            self.to_complex().powc(power.to_complex()),
+        ))
+    }
+
+    /// The largest integer `s` with `s * s <= self`, and the remainder.
+    pub fn exact_integer_sqrt(&self) -> Result<(Number, Number), String> {
+        match self.to_big() {
+            Some(n) if !n.is_negative() => {
+                let root = n.sqrt();
+                let rest = n - &root * &root;
+                Ok((Number::from_big(root), Number::from_big(rest)))
+            }
+            _ => Err(format!("expected non-negative exact integer, got {}", self)),
+        }
+    }
+
+    /// Shared implementation of `gcd` and `lcm`, which accept inexact
+    /// integers and then give inexact results.
+    fn integer_combination(
+        &self,
+        other: &Number,
+        op: fn(&BigInt, &BigInt) -> BigInt,
+    ) -> Result<Number, String> {
+        if !self.is_integer() || !other.is_integer() {
+            return Err("expected integer".to_string());
+        }
+        let exact = |n: &Number| n.to_exact().map(|n| n.to_big().unwrap_or_default());
+        let result = Number::from_big(op(&exact(self)?, &exact(other)?));
+        if self.is_exact() && other.is_exact() {
+            Ok(result)
+        } else {
+            Ok(result.to_inexact())
+        }
+    }
+
+    pub fn gcd(&self, other: &Number) -> Result<Number, String> {
+        self.integer_combination(other, |a, b| a.gcd(b))
+    }
+
+    pub fn lcm(&self, other: &Number) -> Result<Number, String> {
+        self.integer_combination(other, |a, b| a.lcm(b))
+    }
+
+    /// Builds `re + im * i`; an exact zero imaginary part leaves `re` as is.
+    pub fn make_rectangular(re: &Number, im: &Number) -> Number {
+        if im.is_exact() && im.is_zero() {
+            re.clone()
+        } else {
+            Number::from_complex(Complex64::new(re.to_f64(), im.to_f64()))
+        }
+    }
+
+    pub fn make_polar(magnitude: &Number, angle: &Number) -> Number {
+        if angle.is_exact() && angle.is_zero() {
+            magnitude.clone()
+        } else {
+            Number::from_complex(Complex64::from_polar(magnitude.to_f64(), angle.to_f64()))
+        }
+    }
+
+    pub fn real_part(&self) -> Number {
+        match self {
+            Number::Complex(c) => Number::Real(c.re),
+            real => real.clone(),
+        }
+    }
+
+    pub fn imag_part(&self) -> Number {
+        match self {
+            Number::Complex(c) => Number::Real(c.im),
+            _ => Number::Integer(0),
+        }
+    }
+
+    pub fn magnitude(&self) -> Number {
+        match self {
+            Number::Complex(c) => Number::Real(c.norm()),
+            real => real.abs(),
+        }
+    }
+
+    pub fn angle(&self) -> Number {
+        match self {
+            Number::Complex(c) => Number::Real(c.arg()),
+            real if real.is_negative() => Number::Real(std::f64::consts::PI),
+            real if real.is_exact() => Number::Integer(0),
+            _ => Number::Real(0.0),
+        }
+    }
+
+    /// Parses a numeric literal: integers of any size, fractions like `1/3`,
+    /// decimals with optional exponents, `+inf.0`/`-inf.0`/`+nan.0`, complex
+    /// numbers like `1+2i` or `1@0.5`, and the `#e`/`#i` exactness and
+    /// `#x`/`#o`/`#b`/`#d` radix prefixes.
+    pub fn parse(text: &str) -> Option<Number> {
+        let mut text = text;
+        let mut exactness = None;
+        let mut radix = 10;
+        while let Some(prefix) = text.get(..2) {
+            match prefix.to_ascii_lowercase().as_str() {
+                "#e" if exactness.is_none() => exactness = Some(true),
+                "#i" if exactness.is_none() => exactness = Some(false),
+                "#x" if radix == 10 => radix = 16,
+                "#o" if radix == 10 => radix = 8,
+                "#b" if radix == 10 => radix = 2,
+                "#d" if radix == 10 => {}
+                _ => break,
+            }
+            text = &text[2..];
+        }
+        if exactness == Some(true) && radix == 10 {
+            if let Some(exact) = parse_exact_decimal(text) {
+                return Some(Number::from_rational(exact));
+            }
+        }
+        let number = parse_real(text, radix).or_else(|| parse_complex(text, radix))?;
+        match exactness {
+            Some(true) => number.to_exact().ok(),
+            Some(false) => Some(number.to_inexact()),
+            None => Some(number),
+        }
+    }
+}
+
+fn parse_real(text: &str, radix: u32) -> Option<Number> {
+    match text {
+        "+inf.0" => return Some(Number::Real(f64::INFINITY)),
+        "-inf.0" => return Some(Number::Real(f64::NEG_INFINITY)),
+        "+nan.0" | "-nan.0" => return Some(Number::Real(f64::NAN)),
+        _ => {}
+    }
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if digits.is_empty() {
+        return None;
+    }
+    if digits.chars().all(|c| c.is_digit(radix)) {
+        return BigInt::parse_bytes(text.as_bytes(), radix).map(Number::from_big);
+    }
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        if !denominator.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+        let numerator = BigInt::parse_bytes(numerator.as_bytes(), radix)?;
+        let denominator = BigInt::parse_bytes(denominator.as_bytes(), radix)?;
+        if denominator.is_zero() {
+            return None;
+        }
+        return Some(Number::from_rational(BigRational::new(
+            numerator,
+            denominator,
+        )));
+    }
+    let is_decimal = radix == 10
+        && digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+    if is_decimal {
+        text.parse::<f64>().ok().map(Number::Real)
+    } else {
+        None
+    }
+}
+
+/// Reads rectangular (`1+2i`, `-i`) and polar (`2@1.5`) complex literals.
+fn parse_complex(text: &str, radix: u32) -> Option<Number> {
+    if let Some((magnitude, angle)) = text.split_once('@') {
+        return Some(Number::make_polar(
+            &parse_real(magnitude, radix)?,
+            &parse_real(angle, radix)?,
+        ));
+    }
+    let body = text.strip_suffix(['i', 'I'])?;
+    // The imaginary part starts at the last sign that is not part of an
+    // exponent; a purely imaginary literal must start with its sign.
+    let split = body
+        .char_indices()
+        .rev()
+        .find(|&(at, c)| {
+            matches!(c, '+' | '-') && (at == 0 || radix != 10 || !body[..at].ends_with(['e', 'E']))
+        })
+        .map(|(at, _)| at)?;
+    let re = if split == 0 {
+        Number::Integer(0)
+    } else {
+        parse_real(&body[..split], radix)?
+    };
+    let im = match &body[split..] {
+        "+" => Number::Integer(1),
+        "-" => Number::Integer(-1),
+        im => parse_real(im, radix)?,
+    };
+    Some(Number::make_rectangular(&re, &im))
+}
+
+/// Reads a decimal such as `1.25` or `2.5e-3` as the exact fraction it
+/// spells, for `#e` literals.
+fn parse_exact_decimal(text: &str) -> Option<BigRational> {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(at) => (&text[..at], text[at + 1..].parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", whole, fraction);
+    if !fraction.chars().all(|c| c.is_ascii_digit())
+        || digits.trim_start_matches(['+', '-']).is_empty()
+    {
+        return None;
+    }
+    let value = BigRational::from_integer(digits.parse::<BigInt>().ok()?);
+    let scale = exponent - fraction.len() as i32;
+    let ten = BigRational::from_integer(BigInt::from(10));
+    Some(value * num_traits::pow::Pow::pow(&ten, scale))
+}
+
+/// Finds the rational with the smallest denominator in `[lo, hi]`.
+fn simplest_between(lo: &BigRational, hi: &BigRational) -> BigRational {
+    if lo > hi {
+        simplest_between(hi, lo)
+    } else if lo.is_positive() {
+        simplest_positive(lo, hi)
+    } else if hi.is_negative() {
+        -simplest_positive(&-hi, &-lo)
+    } else {
+        BigRational::zero()
+    }
+}
+
+fn simplest_positive(lo: &BigRational, hi: &BigRational) -> BigRational {
+    let floor = lo.floor();
+    if &floor == lo {
+        floor
+    } else if floor < hi.floor() {
+        floor + BigRational::one()
+    } else {
+        let rest = simplest_positive(&(hi - &floor).recip(), &(lo - &floor).recip());
+        floor + rest.recip()
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Integer(i) => write!(f, "{}", i),
+            Number::BigInteger(b) => write!(f, "{}", b),
+            Number::Rational(r) => write!(f, "{}/{}", r.numer(), r.denom()),
+            Number::Real(r) if r.is_nan() => write!(f, "+nan.0"),
+            Number::Real(r) if r.is_infinite() => {
+                write!(f, "{}inf.0", if *r > 0.0 { "+" } else { "-" })
+            }
+            // Debug formatting always shows a decimal point or an exponent,
+            // which keeps inexact numbers distinguishable from exact ones.
+            Number::Real(r) => write!(f, "{:?}", r),
+            Number::Complex(c) => {
+                let im = Number::Real(c.im).to_string();
+                let sign = if im.starts_with(['+', '-']) { "" } else { "+" };
+                write!(f, "{}{}{}i", Number::Real(c.re), sign, im)
+            }
+        }
+    }
+}
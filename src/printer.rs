@@ -0,0 +1,256 @@
+use crate::{LispExpression, LispValue, Pair};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+/// Formats a value as `write` does (`{}` on a `LispValue`) or as `display`
+/// does, which prints strings and characters without their syntax.
+pub struct Printed<'a> {
+    value: &'a LispValue,
+    write: bool,
+}
+
+impl LispValue {
+    pub fn display(&self) -> Printed<'_> {
+        Printed {
+            value: self,
+            write: false,
+        }
+    }
+}
+
+impl fmt::Display for LispValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Printed {
+            value: self,
+            write: true,
+        }
+        .fmt(f)
+    }
+}
+
+impl fmt::Display for Printed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut printer = Printer {
+            f,
+            write: self.write,
+            labels: cycle_targets(self.value),
+            next_label: 0,
+        };
+        printer.print(self.value)
+    }
+}
+
+/// Walks the value and returns the pairs that are reachable from
+/// themselves; these are the ones printed with `#n=` datum labels.
+fn cycle_targets(value: &LispValue) -> HashMap<*const Pair, Option<usize>> {
+    fn scan(
+        value: &LispValue,
+        active: &mut HashSet<*const Pair>,
+        done: &mut HashSet<*const Pair>,
+        targets: &mut HashMap<*const Pair, Option<usize>>,
+    ) {
+        // Follow the cdr chain iteratively so long lists don't recurse deeply.
+        let mut chain = Vec::new();
+        let mut current = value.clone();
+        while let LispValue::Pair(pair) = current {
+            let ptr = Rc::as_ptr(&pair);
+            if active.contains(&ptr) {
+                targets.insert(ptr, None);
+                break;
+            }
+            if !done.insert(ptr) {
+                break;
+            }
+            active.insert(ptr);
+            chain.push(ptr);
+            scan(&pair.car.borrow(), active, done, targets);
+            current = pair.cdr.borrow().clone();
+        }
+        for ptr in chain {
+            active.remove(&ptr);
+        }
+    }
+
+    let mut targets = HashMap::new();
+    scan(
+        value,
+        &mut HashSet::new(),
+        &mut HashSet::new(),
+        &mut targets,
+    );
+    targets
+}
+
+struct Printer<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    write: bool,
+    /// Cycle targets and the label number once the first one is printed.
+    labels: HashMap<*const Pair, Option<usize>>,
+    next_label: usize,
+}
+
+impl Printer<'_, '_> {
+    fn print(&mut self, value: &LispValue) -> fmt::Result {
+        match value {
+            LispValue::Number(num) => write!(self.f, "{}", num),
+            LispValue::Boolean(b) => write!(self.f, "{}", if *b { "#t" } else { "#f" }),
+            LispValue::Char(c) if self.write => write_char(self.f, *c),
+            LispValue::Char(c) => write!(self.f, "{}", c),
+            LispValue::String(string) if self.write => write_string(self.f, string),
+            LispValue::String(string) => write!(self.f, "{}", string),
+            LispValue::Symbol(sym) => write!(self.f, "{}", sym),
+            LispValue::Lambda(closure) => match &closure.name {
+                Some(name) => write!(self.f, "#<procedure {}>", name),
+                None => write!(self.f, "#<procedure>"),
+            },
+            LispValue::Builtin(name, _) | LispValue::Control(name, _) => {
+                write!(self.f, "#<procedure {}>", name)
+            }
+            LispValue::Continuation(_) => write!(self.f, "#<continuation>"),
+            LispValue::Values(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.f, " ")?;
+                    }
+                    let printed = Printed {
+                        value,
+                        write: self.write,
+                    };
+                    write!(self.f, "{}", printed)?;
+                }
+                Ok(())
+            }
+            LispValue::Syntax(rules) => match &rules.name {
+                Some(name) => write!(self.f, "#<syntax {}>", name),
+                None => write!(self.f, "#<syntax>"),
+            },
+            LispValue::Transformer(closure) => match &closure.name {
+                Some(name) => write!(self.f, "#<syntax {}>", name),
+                None => write!(self.f, "#<syntax>"),
+            },
+            LispValue::SyntaxObject(syntax) => write!(self.f, "#<syntax {}>", syntax.expr),
+            LispValue::PatternVariable(_) => write!(self.f, "#<pattern variable>"),
+            LispValue::Macro(closure) => match &closure.name {
+                Some(name) => write!(self.f, "#<macro {}>", name),
+                None => write!(self.f, "#<macro>"),
+            },
+            LispValue::Pair(pair) => self.print_pair(pair),
+            LispValue::Error(err) => {
+                write!(self.f, "#<error ")?;
+                write_string(self.f, &err.message())?;
+                write!(self.f, ">")
+            }
+            LispValue::Nil => write!(self.f, "()"),
+            LispValue::Unspecified => write!(self.f, "#<unspecified>"),
+        }
+    }
+
+    /// Prints a `#n=` label before a cycle target the first time it is
+    /// reached; returns false when it was already printed as `#n#`.
+    fn label(&mut self, pair: &Rc<Pair>) -> Result<bool, fmt::Error> {
+        match self.labels.get_mut(&Rc::as_ptr(pair)) {
+            Some(Some(label)) => {
+                write!(self.f, "#{}#", label)?;
+                Ok(false)
+            }
+            Some(label) => {
+                *label = Some(self.next_label);
+                write!(self.f, "#{}=", self.next_label)?;
+                self.next_label += 1;
+                Ok(true)
+            }
+            None => Ok(true),
+        }
+    }
+
+    fn print_pair(&mut self, pair: &Rc<Pair>) -> fmt::Result {
+        if !self.label(pair)? {
+            return Ok(());
+        }
+        write!(self.f, "(")?;
+        self.print(&pair.car.borrow())?;
+        let mut rest = pair.cdr.borrow().clone();
+        loop {
+            match rest {
+                LispValue::Nil => break,
+                // A labelled pair in the tail has to be written in dotted
+                // form so that its label has somewhere to go.
+                LispValue::Pair(next) if !self.labels.contains_key(&Rc::as_ptr(&next)) => {
+                    write!(self.f, " ")?;
+                    self.print(&next.car.borrow())?;
+                    rest = next.cdr.borrow().clone();
+                }
+                tail => {
+                    write!(self.f, " . ")?;
+                    self.print(&tail)?;
+                    break;
+                }
+            }
+        }
+        write!(self.f, ")")
+    }
+}
+
+fn write_char(f: &mut fmt::Formatter, c: char) -> fmt::Result {
+    let name = match c {
+        ' ' => "space",
+        '\n' => "newline",
+        '\t' => "tab",
+        '\r' => "return",
+        '\0' => "null",
+        '\u{7}' => "alarm",
+        '\u{8}' => "backspace",
+        '\u{1b}' => "escape",
+        '\u{7f}' => "delete",
+        c if c.is_control() => return write!(f, "#\\x{:x}", c as u32),
+        c => return write!(f, "#\\{}", c),
+    };
+    write!(f, "#\\{}", name)
+}
+
+fn write_string(f: &mut fmt::Formatter, string: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in string.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            c if c.is_control() => write!(f, "\\x{:x};", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Source code is printed the way `write` prints the data it denotes.
+impl fmt::Display for LispExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LispExpression::Number(num) => write!(f, "{}", num),
+            LispExpression::Boolean(b) => write!(f, "{}", if *b { "#t" } else { "#f" }),
+            LispExpression::Char(c) => write_char(f, *c),
+            LispExpression::String(string) => write_string(f, string),
+            LispExpression::Symbol(sym) => write!(f, "{}", sym),
+            LispExpression::List(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+            LispExpression::DottedList(items, tail) => {
+                write!(f, "(")?;
+                for item in items.iter() {
+                    write!(f, "{} ", item)?;
+                }
+                write!(f, ". {})", tail)
+            }
+        }
+    }
+}
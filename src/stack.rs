@@ -0,0 +1,187 @@
+use crate::lexer::Span;
+use crate::{LispValue, Pending};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::iter;
+use std::mem;
+use std::rc::Rc;
+
+/// A procedure call in progress: what was called and where from.
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub procedure: LispValue,
+    pub span: Option<Span>,
+}
+
+impl CallFrame {
+    pub fn new(procedure: &LispValue, span: Option<&Span>) -> CallFrame {
+        CallFrame {
+            procedure: procedure.clone(),
+            span: span.cloned(),
+        }
+    }
+}
+
+impl fmt::Display for CallFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.procedure {
+            LispValue::Lambda(closure) => {
+                write!(f, "{}", closure.name.as_deref().unwrap_or("<anonymous>"))?
+            }
+            LispValue::Builtin(name, _) | LispValue::Control(name, _) => write!(f, "{}", name)?,
+            other => write!(f, "{}", other)?,
+        }
+        match &self.span {
+            Some(span) => write!(f, " at {}", span),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The rest of a computation: the frames waiting for the value being
+/// computed, innermost first. Frames are shared rather than copied, so a
+/// continuation can be captured cheaply and resumed any number of times.
+#[derive(Debug, Clone, Default)]
+pub struct Continuation(Option<Rc<Link>>);
+
+#[derive(Debug)]
+struct Link {
+    frame: Pending,
+    next: Continuation,
+}
+
+impl Continuation {
+    pub fn push(&mut self, frame: Pending) {
+        let next = mem::take(self);
+        self.0 = Some(Rc::new(Link { frame, next }));
+    }
+
+    pub fn pop(&mut self) -> Option<Pending> {
+        let link = self.0.take()?;
+        match Rc::try_unwrap(link) {
+            Ok(mut link) => {
+                *self = mem::take(&mut link.next);
+                Some(link.frame)
+            }
+            // The frame is shared with a captured continuation.
+            Err(link) => {
+                *self = link.next.clone();
+                Some(link.frame.clone())
+            }
+        }
+    }
+
+    /// Records a call. A call made in tail position replaces its caller's
+    /// record, so loops written as tail calls run in constant space.
+    pub fn enter(&mut self, call: CallFrame) {
+        if matches!(self.frames().next(), Some(Pending::Call(_))) {
+            self.pop();
+        }
+        self.push(Pending::Call(call));
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = &Pending> {
+        iter::successors(self.0.as_deref(), |link| link.next.0.as_deref()).map(|link| &link.frame)
+    }
+
+    /// The calls in progress, most recent first.
+    pub fn backtrace(&self) -> Vec<CallFrame> {
+        self.frames()
+            .filter_map(|frame| match frame {
+                Pending::Call(call) => Some(call.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn same(&self, other: &Continuation) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Unlinks the frames iteratively so that dropping the continuation of a
+/// deep recursion doesn't overflow the stack.
+impl Drop for Continuation {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(link) = next {
+            match Rc::try_unwrap(link) {
+                Ok(mut link) => next = link.next.0.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// The runs of the evaluator in progress, innermost last. Runs nest when
+    /// Rust code, such as a macro expander or `load`, calls back into Lisp.
+    static RUNS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+    static NEXT_RUN: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A run of the evaluator, in progress until it is dropped.
+pub struct Run {
+    id: usize,
+}
+
+impl Run {
+    pub fn begin() -> Run {
+        let id = NEXT_RUN.with(|next| next.replace(next.get() + 1));
+        RUNS.with(|runs| runs.borrow_mut().push(id));
+        Run { id }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        RUNS.with(|runs| runs.borrow_mut().pop());
+    }
+}
+
+/// A continuation captured by `call/cc`, along with the run it belongs to.
+/// Its frames end where that run does, so only that run can resume it.
+#[derive(Debug, Clone)]
+pub struct Captured {
+    pub kont: Continuation,
+    run: usize,
+    top_level: bool,
+}
+
+impl Captured {
+    /// Captures `kont`, which belongs to the innermost run.
+    pub fn new(kont: Continuation) -> Captured {
+        let (run, top_level) = RUNS.with(|runs| {
+            let runs = runs.borrow();
+            (runs.last().copied().unwrap_or_default(), runs.len() == 1)
+        });
+        Captured {
+            kont,
+            run,
+            top_level,
+        }
+    }
+
+    /// The run that can resume the continuation, if it is still in progress.
+    /// Each top-level form runs separately, so a continuation captured at top
+    /// level can be resumed by whichever top-level run is in progress, like
+    /// returning to the top level of a REPL.
+    pub fn owner(&self) -> Option<usize> {
+        RUNS.with(|runs| {
+            let runs = runs.borrow();
+            if self.top_level {
+                runs.first().copied()
+            } else {
+                runs.contains(&self.run).then_some(self.run)
+            }
+        })
+    }
+}
@@ -0,0 +1,706 @@
+use crate::error::LispError;
+use crate::lexer::Span;
+use crate::{Closure, Environment, Identifier, LispExpression, LispValue, List, Rename};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A `syntax-rules` transformer: patterns tried in order, each with the
+/// template that replaces a form it matches.
+#[derive(Debug)]
+pub struct SyntaxRules {
+    pub name: Option<String>,
+    ellipsis: String,
+    literals: Vec<Identifier>,
+    rules: Vec<(LispExpression, LispExpression)>,
+    /// Where the macro was defined. Identifiers its templates insert refer
+    /// to the bindings visible here.
+    env: Environment,
+}
+
+/// A piece of code as a value, as `syntax-case` transformers see it: the
+/// expression, with its source locations and renamed identifiers, and the
+/// environment its other identifiers are resolved in.
+#[derive(Debug)]
+pub struct SyntaxObject {
+    pub expr: LispExpression,
+    pub env: Environment,
+}
+
+/// What a pattern variable matched. Variables under `n` ellipses are bound
+/// to `n` levels of `Many`.
+#[derive(Debug, Clone)]
+pub enum Binding {
+    One(LispExpression),
+    Many(Vec<Binding>),
+}
+
+pub type Bindings = HashMap<String, Binding>;
+
+/// A call to a procedural macro in progress.
+struct Context {
+    mark: usize,
+    /// Where the transformer was defined.
+    env: Environment,
+    /// Where the macro was used.
+    use_env: Environment,
+    span: Option<Span>,
+}
+
+thread_local! {
+    static NEXT_MARK: Cell<usize> = const { Cell::new(0) };
+    /// The procedural macro calls in progress, innermost last. `syntax`
+    /// templates rename the identifiers they insert for the innermost one.
+    static EXPANDING: RefCell<Vec<Context>> = const { RefCell::new(Vec::new()) };
+}
+
+fn next_mark() -> usize {
+    NEXT_MARK.with(|mark| mark.replace(mark.get() + 1))
+}
+
+/// Compares identifiers the way `syntax-rules` compares literals: they must
+/// have the same name and refer to the same thing, or both be unbound.
+/// Bindings are compared by value, as environments don't expose locations.
+pub fn free_identifier_eq(
+    a: &Identifier,
+    a_env: &Environment,
+    b: &Identifier,
+    b_env: &Environment,
+) -> bool {
+    a.name == b.name
+        && match (a_env.lookup(a), b_env.lookup(b)) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a.eqv(&b),
+            _ => false,
+        }
+}
+
+fn symbol(expr: &LispExpression) -> Option<&Identifier> {
+    match expr {
+        LispExpression::Symbol(ident) => Some(ident),
+        _ => None,
+    }
+}
+
+/// Splits a list or improper list into its elements and tail.
+fn sequence(expr: &LispExpression) -> Option<(&[LispExpression], Option<&LispExpression>)> {
+    match expr {
+        LispExpression::List(items) => Some((items, None)),
+        LispExpression::DottedList(items, tail) => Some((items, Some(tail))),
+        _ => None,
+    }
+}
+
+/// Rebuilds a list from elements and an optional tail.
+fn make_sequence(
+    items: Vec<LispExpression>,
+    tail: Option<LispExpression>,
+    span: Option<&Span>,
+) -> LispExpression {
+    match tail {
+        None => LispExpression::List(List {
+            items: items.into(),
+            span: span.cloned(),
+        }),
+        Some(tail) if items.is_empty() => tail,
+        Some(LispExpression::List(rest)) => {
+            let mut items = items;
+            items.extend(rest.iter().cloned());
+            make_sequence(items, None, span)
+        }
+        Some(LispExpression::DottedList(rest, tail)) => {
+            let mut items = items;
+            items.extend(rest.iter().cloned());
+            LispExpression::DottedList(items.into(), tail)
+        }
+        Some(tail) => LispExpression::DottedList(items.into(), Box::new(tail)),
+    }
+}
+
+impl SyntaxRules {
+    /// Parses `(syntax-rules (literal ...) (pattern template) ...)`, or
+    /// `(syntax-rules ellipsis (literal ...) ...)` with a custom ellipsis.
+    pub fn new(
+        spec: &[LispExpression],
+        name: Option<&str>,
+        env: &Environment,
+    ) -> Result<SyntaxRules, LispError> {
+        let (ellipsis, rest) = match spec.get(1) {
+            Some(LispExpression::Symbol(ellipsis)) => (ellipsis.name.clone(), &spec[2..]),
+            _ => ("...".to_string(), &spec[1..]),
+        };
+        let literals = match rest.first() {
+            Some(LispExpression::List(literals)) => literals
+                .iter()
+                .map(|literal| {
+                    symbol(literal)
+                        .cloned()
+                        .ok_or_else(|| LispError::syntax("Invalid literal in syntax-rules"))
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err(LispError::syntax("Invalid syntax-rules literals")),
+        };
+        let rules = rest[1..]
+            .iter()
+            .map(|rule| match rule {
+                LispExpression::List(rule) if rule.len() == 2 && sequence(&rule[0]).is_some() => {
+                    Ok((rule[0].clone(), rule[1].clone()))
+                }
+                _ => Err(LispError::syntax("Invalid syntax-rules rule")),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(SyntaxRules {
+            name: name.map(str::to_string),
+            ellipsis,
+            literals,
+            rules,
+            env: env.clone(),
+        })
+    }
+
+    /// Rewrites `form`, a use of this macro in `use_env`, with the first
+    /// rule whose pattern matches it.
+    pub fn expand(&self, form: &List, use_env: &Environment) -> Result<LispExpression, LispError> {
+        let matcher = Matcher {
+            ellipsis: &self.ellipsis,
+            literals: &self.literals,
+            env: &self.env,
+        };
+        for (pattern, template) in &self.rules {
+            let mut bindings = Bindings::new();
+            let (patterns, tail) = sequence(pattern).expect("rules are checked when parsed");
+            if matcher.match_sequence(
+                &patterns[1..],
+                tail,
+                &form[1..],
+                None,
+                use_env,
+                &mut bindings,
+            ) {
+                let mut expansion = Expansion {
+                    literals: &self.literals,
+                    context: Some((next_mark(), self.env.clone())),
+                    renames: HashMap::new(),
+                    span: form.span.clone(),
+                };
+                return expansion.instantiate(template, &bindings, Some(&self.ellipsis));
+            }
+        }
+        Err(LispError::syntax(format!(
+            "{}: no rule matches {}",
+            self.name.as_deref().unwrap_or("syntax-rules"),
+            LispExpression::List(form.clone())
+        )))
+    }
+}
+
+/// Matches forms against `syntax-rules` and `syntax-case` patterns.
+struct Matcher<'a> {
+    ellipsis: &'a str,
+    literals: &'a [Identifier],
+    /// Where the literals are resolved.
+    env: &'a Environment,
+}
+
+impl Matcher<'_> {
+    fn is_literal(&self, ident: &Identifier) -> bool {
+        self.literals
+            .iter()
+            .any(|literal| literal.key() == ident.key())
+    }
+
+    fn is_ellipsis(&self, expr: &LispExpression) -> bool {
+        matches!(symbol(expr), Some(ident) if ident.name == self.ellipsis && !self.is_literal(ident))
+    }
+
+    fn match_pattern(
+        &self,
+        pattern: &LispExpression,
+        form: &LispExpression,
+        use_env: &Environment,
+        bindings: &mut Bindings,
+    ) -> bool {
+        if let LispExpression::Symbol(ident) = pattern {
+            if self.is_literal(ident) {
+                return matches!(form, LispExpression::Symbol(input)
+                    if free_identifier_eq(ident, self.env, input, use_env));
+            }
+            if ident.name != "_" {
+                bindings.insert(ident.key().to_string(), Binding::One(form.clone()));
+            }
+            return true;
+        }
+        match (sequence(pattern), sequence(form)) {
+            (Some((patterns, tail)), Some((items, form_tail))) => {
+                self.match_sequence(patterns, tail, items, form_tail, use_env, bindings)
+            }
+            // A pattern with a tail also matches a shorter form, leaving the
+            // tail to match what remains.
+            (Some((patterns, Some(tail))), None) => {
+                self.match_sequence(patterns, Some(tail), &[], Some(form), use_env, bindings)
+            }
+            (Some(_), None) | (None, Some(_)) => false,
+            (None, None) => LispValue::from_datum(pattern).equal(&LispValue::from_datum(form)),
+        }
+    }
+
+    /// Matches the elements and tail of a list pattern against those of a
+    /// form. At most one element of the pattern may be followed by an
+    /// ellipsis; it matches as many elements as the rest of the pattern
+    /// leaves over.
+    fn match_sequence(
+        &self,
+        patterns: &[LispExpression],
+        tail: Option<&LispExpression>,
+        items: &[LispExpression],
+        form_tail: Option<&LispExpression>,
+        use_env: &Environment,
+        bindings: &mut Bindings,
+    ) -> bool {
+        let ellipsis =
+            (0..patterns.len()).find(|&i| patterns.get(i + 1).is_some_and(|p| self.is_ellipsis(p)));
+        let (before, repeated, after) = match ellipsis {
+            Some(i) => (&patterns[..i], Some(&patterns[i]), &patterns[i + 2..]),
+            None => (patterns, None, &[][..]),
+        };
+        let fixed = before.len() + after.len();
+        if items.len() < fixed || (repeated.is_none() && tail.is_none() && items.len() != fixed) {
+            return false;
+        }
+        if !before
+            .iter()
+            .zip(items)
+            .all(|(pattern, item)| self.match_pattern(pattern, item, use_env, bindings))
+        {
+            return false;
+        }
+        // Without an ellipsis, a tail pattern takes whatever follows the
+        // fixed elements.
+        let repeat_end = match repeated {
+            Some(_) => items.len() - after.len(),
+            None => before.len(),
+        };
+        if let Some(repeated) = repeated {
+            let mut matches = Vec::new();
+            for item in &items[before.len()..repeat_end] {
+                let mut item_bindings = Bindings::new();
+                if !self.match_pattern(repeated, item, use_env, &mut item_bindings) {
+                    return false;
+                }
+                matches.push(item_bindings);
+            }
+            let mut vars = Vec::new();
+            self.pattern_vars(repeated, &mut vars);
+            for var in vars {
+                let values = matches
+                    .iter_mut()
+                    .map(|item_bindings| {
+                        item_bindings
+                            .remove(&var)
+                            .expect("every match binds every variable")
+                    })
+                    .collect();
+                bindings.insert(var, Binding::Many(values));
+            }
+        }
+        let after_items = &items[repeat_end..];
+        let (after_items, rest) = match repeated {
+            Some(_) => (after_items, &[][..]),
+            None => (&after_items[..0], after_items),
+        };
+        if !after
+            .iter()
+            .zip(after_items)
+            .all(|(pattern, item)| self.match_pattern(pattern, item, use_env, bindings))
+        {
+            return false;
+        }
+        match tail {
+            Some(tail) => {
+                let rest = make_sequence(rest.to_vec(), form_tail.cloned(), None);
+                self.match_pattern(tail, &rest, use_env, bindings)
+            }
+            None => form_tail.is_none(),
+        }
+    }
+
+    /// Collects the keys of the pattern variables in `pattern`.
+    fn pattern_vars(&self, pattern: &LispExpression, vars: &mut Vec<String>) {
+        match pattern {
+            LispExpression::Symbol(ident)
+                if ident.name != "_" && !self.is_literal(ident) && !self.is_ellipsis(pattern) =>
+            {
+                vars.push(ident.key().to_string());
+            }
+            LispExpression::List(items) => {
+                items.iter().for_each(|item| self.pattern_vars(item, vars))
+            }
+            LispExpression::DottedList(items, tail) => {
+                items.iter().for_each(|item| self.pattern_vars(item, vars));
+                self.pattern_vars(tail, vars);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The state of filling in a template. Within a macro expansion, every
+/// identifier the template inserts is renamed with the expansion's mark and
+/// resolved in the macro's environment, so the copies of one identifier
+/// within an expansion agree with each other but with nothing outside it.
+struct Expansion<'a> {
+    literals: &'a [Identifier],
+    /// The mark and macro environment, or `None` outside any expansion,
+    /// where identifiers are inserted as they are.
+    context: Option<(usize, Environment)>,
+    renames: HashMap<String, Identifier>,
+    /// The location of the macro use, given to the code the template inserts.
+    span: Option<Span>,
+}
+
+impl Expansion<'_> {
+    /// Fills in `template`. `ellipsis` is `None` inside `(... template)`,
+    /// where ellipses are copied literally.
+    fn instantiate(
+        &mut self,
+        template: &LispExpression,
+        bindings: &Bindings,
+        ellipsis: Option<&str>,
+    ) -> Result<LispExpression, LispError> {
+        match template {
+            LispExpression::Symbol(ident) => match bindings.get(ident.key()) {
+                Some(Binding::One(form)) => Ok(form.clone()),
+                Some(Binding::Many(_)) => Err(LispError::syntax(format!(
+                    "pattern variable {} used without an ellipsis",
+                    ident
+                ))),
+                None => Ok(LispExpression::Symbol(self.rename(ident))),
+            },
+            LispExpression::List(items) => match &items[..] {
+                [escape, template] if ellipsis.is_some() && self.is_ellipsis(escape, ellipsis) => {
+                    self.instantiate(template, bindings, None)
+                }
+                items => self.instantiate_sequence(items, None, bindings, ellipsis),
+            },
+            LispExpression::DottedList(items, tail) => {
+                self.instantiate_sequence(items, Some(tail), bindings, ellipsis)
+            }
+            _ => Ok(template.clone()),
+        }
+    }
+
+    fn instantiate_sequence(
+        &mut self,
+        items: &[LispExpression],
+        tail: Option<&LispExpression>,
+        bindings: &Bindings,
+        ellipsis: Option<&str>,
+    ) -> Result<LispExpression, LispError> {
+        let mut output = Vec::new();
+        let mut i = 0;
+        while i < items.len() {
+            let depth = items[i + 1..]
+                .iter()
+                .take_while(|item| self.is_ellipsis(item, ellipsis))
+                .count();
+            if depth == 0 {
+                output.push(self.instantiate(&items[i], bindings, ellipsis)?);
+            } else {
+                self.repeat(&items[i], bindings, depth, ellipsis, &mut output)?;
+            }
+            i += depth + 1;
+        }
+        let tail = tail
+            .map(|tail| self.instantiate(tail, bindings, ellipsis))
+            .transpose()?;
+        Ok(make_sequence(output, tail, self.span.as_ref()))
+    }
+
+    /// Fills in `template` once for each element matched by the pattern
+    /// variables under the ellipsis, `depth` ellipses deep.
+    fn repeat(
+        &mut self,
+        template: &LispExpression,
+        bindings: &Bindings,
+        depth: usize,
+        ellipsis: Option<&str>,
+        output: &mut Vec<LispExpression>,
+    ) -> Result<(), LispError> {
+        let mut vars = Vec::new();
+        template_vars(template, bindings, &mut vars);
+        let mut length = None;
+        for var in &vars {
+            let count = match &bindings[var] {
+                Binding::Many(values) => values.len(),
+                Binding::One(_) => continue,
+            };
+            if length.is_some_and(|length| length != count) {
+                return Err(LispError::syntax(format!(
+                    "pattern variables under one ellipsis matched different numbers of forms in {}",
+                    template
+                )));
+            }
+            length = Some(count);
+        }
+        let length = length.ok_or_else(|| {
+            LispError::syntax(format!("no pattern variable to repeat in {}", template))
+        })?;
+        for index in 0..length {
+            let mut item_bindings = bindings.clone();
+            for var in &vars {
+                if let Binding::Many(values) = &bindings[var] {
+                    item_bindings.insert(var.clone(), values[index].clone());
+                }
+            }
+            if depth == 1 {
+                output.push(self.instantiate(template, &item_bindings, ellipsis)?);
+            } else {
+                self.repeat(template, &item_bindings, depth - 1, ellipsis, output)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_ellipsis(&self, expr: &LispExpression, ellipsis: Option<&str>) -> bool {
+        matches!((symbol(expr), ellipsis), (Some(ident), Some(ellipsis))
+            if ident.name == ellipsis && !self.literals.iter().any(|literal| literal.key() == ident.key()))
+    }
+
+    /// The renamed copy of an identifier the template inserts.
+    fn rename(&mut self, ident: &Identifier) -> Identifier {
+        let (mark, env) = match &self.context {
+            Some(context) => context,
+            None => return ident.clone(),
+        };
+        let span = &self.span;
+        self.renames
+            .entry(ident.key().to_string())
+            .or_insert_with(|| Identifier {
+                name: ident.name.clone(),
+                span: span.clone(),
+                rename: Some(Rc::new(Rename {
+                    key: format!("{} {}", ident.key(), mark),
+                    mark: *mark,
+                    env: env.clone(),
+                    original: ident.clone(),
+                })),
+            })
+            .clone()
+    }
+}
+
+/// Collects the keys of the pattern variables that occur in `template`.
+fn template_vars(template: &LispExpression, bindings: &Bindings, vars: &mut Vec<String>) {
+    match template {
+        LispExpression::Symbol(ident)
+            if bindings.contains_key(ident.key()) && !vars.iter().any(|var| var == ident.key()) =>
+        {
+            vars.push(ident.key().to_string());
+        }
+        LispExpression::List(items) => items
+            .iter()
+            .for_each(|item| template_vars(item, bindings, vars)),
+        LispExpression::DottedList(items, tail) => {
+            items
+                .iter()
+                .for_each(|item| template_vars(item, bindings, vars));
+            template_vars(tail, bindings, vars);
+        }
+        _ => {}
+    }
+}
+
+/// Expands a use of a procedural macro by calling its transformer with the
+/// form as a syntax object.
+pub fn expand_procedure(
+    transformer: &Rc<Closure>,
+    form: &List,
+    use_env: &Environment,
+) -> Result<LispExpression, LispError> {
+    let input = LispValue::SyntaxObject(Rc::new(SyntaxObject {
+        expr: LispExpression::List(form.clone()),
+        env: use_env.clone(),
+    }));
+    EXPANDING.with(|expanding| {
+        expanding.borrow_mut().push(Context {
+            mark: next_mark(),
+            env: transformer.env.clone(),
+            use_env: use_env.clone(),
+            span: form.span.clone(),
+        })
+    });
+    let output = crate::apply(&LispValue::Lambda(transformer.clone()), &[input]);
+    EXPANDING.with(|expanding| expanding.borrow_mut().pop());
+    LispExpression::from_value(&output?, form.span.as_ref())
+}
+
+/// Returns the code a value stands for, with the environment its
+/// identifiers belong to. Values other than syntax objects are data from
+/// the code running in `env`.
+pub fn syntax_parts(
+    value: &LispValue,
+    env: &Environment,
+) -> Result<(LispExpression, Environment), LispError> {
+    match value {
+        LispValue::SyntaxObject(syntax) => Ok((syntax.expr.clone(), syntax.env.clone())),
+        value => Ok((LispExpression::from_value(value, None)?, env.clone())),
+    }
+}
+
+/// Parses the `(literal ...)` list of `syntax-case`.
+pub fn parse_literals(literals: &LispExpression) -> Result<Vec<Identifier>, LispError> {
+    match literals {
+        LispExpression::List(literals) => literals
+            .iter()
+            .map(|literal| {
+                symbol(literal)
+                    .cloned()
+                    .ok_or_else(|| LispError::syntax("Invalid literal in syntax-case"))
+            })
+            .collect(),
+        _ => Err(LispError::syntax("Invalid syntax-case literals")),
+    }
+}
+
+/// Matches `form`, which belongs to `form_env`, against a `syntax-case` or
+/// `with-syntax` pattern written in `env`.
+pub fn match_syntax(
+    pattern: &LispExpression,
+    form: &LispExpression,
+    literals: &[Identifier],
+    env: &Environment,
+    form_env: &Environment,
+) -> Option<Bindings> {
+    let matcher = Matcher {
+        ellipsis: "...",
+        literals,
+        env,
+    };
+    let mut bindings = Bindings::new();
+    matcher
+        .match_pattern(pattern, form, form_env, &mut bindings)
+        .then_some(bindings)
+}
+
+/// Evaluates `(syntax template)`, filling in the pattern variables bound in
+/// `env`.
+pub fn syntax_template(
+    template: &LispExpression,
+    env: &Environment,
+) -> Result<LispValue, LispError> {
+    let mut bindings = Bindings::new();
+    pattern_variables(template, env, &mut bindings);
+    let (context, use_env, span) = EXPANDING.with(|expanding| match expanding.borrow().last() {
+        Some(context) => (
+            Some((context.mark, context.env.clone())),
+            context.use_env.clone(),
+            context.span.clone(),
+        ),
+        None => (None, env.clone(), None),
+    });
+    let mut expansion = Expansion {
+        literals: &[],
+        context,
+        renames: HashMap::new(),
+        span,
+    };
+    Ok(LispValue::SyntaxObject(Rc::new(SyntaxObject {
+        expr: expansion.instantiate(template, &bindings, Some("..."))?,
+        env: use_env,
+    })))
+}
+
+/// Collects the pattern variables that `template` refers to.
+fn pattern_variables(template: &LispExpression, env: &Environment, bindings: &mut Bindings) {
+    match template {
+        LispExpression::Symbol(ident) => {
+            if let Some(LispValue::PatternVariable(binding)) = env.lookup(ident) {
+                bindings.insert(ident.key().to_string(), (*binding).clone());
+            }
+        }
+        LispExpression::List(items) => items
+            .iter()
+            .for_each(|item| pattern_variables(item, env, bindings)),
+        LispExpression::DottedList(items, tail) => {
+            items
+                .iter()
+                .for_each(|item| pattern_variables(item, env, bindings));
+            pattern_variables(tail, env, bindings);
+        }
+        _ => {}
+    }
+}
+
+/// An identifier named `name` with the lexical context of `context`: it is
+/// renamed the way `context` was, so it binds and refers as if it had been
+/// written in the same place.
+fn with_context(name: &str, span: Option<&Span>, context: &Identifier) -> Identifier {
+    let rename = context.rename.as_ref().map(|rename| {
+        let original = with_context(name, span, &rename.original);
+        Rc::new(Rename {
+            key: format!("{} {}", original.key(), rename.mark),
+            mark: rename.mark,
+            env: rename.env.clone(),
+            original,
+        })
+    });
+    Identifier {
+        name: name.to_string(),
+        span: span.cloned(),
+        rename,
+    }
+}
+
+/// Implements `datum->syntax`: gives the symbols in `datum` the lexical
+/// context of the identifier `context`.
+pub fn datum_to_syntax(context: &SyntaxObject, datum: &LispValue) -> Result<LispValue, LispError> {
+    fn recontext(expr: &LispExpression, context: &Identifier) -> LispExpression {
+        match expr {
+            LispExpression::Symbol(ident) => {
+                LispExpression::Symbol(with_context(&ident.name, ident.span.as_ref(), context))
+            }
+            LispExpression::List(list) => LispExpression::List(List {
+                items: list.iter().map(|item| recontext(item, context)).collect(),
+                span: list.span.clone(),
+            }),
+            LispExpression::DottedList(items, tail) => LispExpression::DottedList(
+                items.iter().map(|item| recontext(item, context)).collect(),
+                Box::new(recontext(tail, context)),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    let ident = match &context.expr {
+        LispExpression::Symbol(ident) => ident,
+        _ => return Err(LispError::syntax("datum->syntax: expected an identifier")),
+    };
+    let expr = LispExpression::from_value(datum, ident.span.as_ref())?;
+    Ok(LispValue::SyntaxObject(Rc::new(SyntaxObject {
+        expr: recontext(&expr, ident),
+        env: context.env.clone(),
+    })))
+}
+
+/// Makes `count` identifiers unlike any other, for `generate-temporaries`.
+pub fn generate_temporaries(count: usize, env: &Environment) -> Vec<LispValue> {
+    (0..count)
+        .map(|_| {
+            let mark = next_mark();
+            let expr = LispExpression::Symbol(Identifier {
+                name: "temp".to_string(),
+                span: None,
+                rename: Some(Rc::new(Rename {
+                    key: format!("temp {}", mark),
+                    mark,
+                    env: env.clone(),
+                    original: Identifier::from("temp"),
+                })),
+            });
+            LispValue::SyntaxObject(Rc::new(SyntaxObject {
+                expr,
+                env: env.clone(),
+            }))
+        })
+        .collect()
+}